@@ -1,13 +1,350 @@
-use std::fs;
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    env,
+    fmt::Display,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    process,
+    str::FromStr,
+};
+
+#[derive(Debug)]
+enum LedgerError {
+    Io(io::Error),
+    InvalidNumber(usize), // line number
+    Overflow(usize),      // line number
+}
+
+impl Display for LedgerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::InvalidNumber(line) => write!(f, "Invalid calorie count on line {}", line),
+            Self::Overflow(line) => write!(f, "Calorie total overflows on line {}", line),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Elf {
+    index: usize, // 1-based position in the input
+    items: usize,
+    total: u64,
+}
+
+// Reads elves one at a time, without holding more than one line in memory
+struct Elves<R> {
+    lines: io::Lines<R>,
+    line_no: usize,
+    next_index: usize,
+}
+
+impl<R: BufRead> Elves<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line_no: 0,
+            next_index: 1,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Elves<R> {
+    type Item = Result<Elf, LedgerError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut elf: Option<Elf> = None;
+        for line in self.lines.by_ref() {
+            self.line_no += 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(LedgerError::Io(e))),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                if elf.is_some() {
+                    break;
+                }
+                continue;
+            }
+            let Ok(calories) = line.parse::<u64>() else {
+                return Some(Err(LedgerError::InvalidNumber(self.line_no)));
+            };
+            let elf = elf.get_or_insert(Elf {
+                index: self.next_index,
+                items: 0,
+                total: 0,
+            });
+            elf.items += 1;
+            let Some(total) = elf.total.checked_add(calories) else {
+                return Some(Err(LedgerError::Overflow(self.line_no)));
+            };
+            elf.total = total;
+        }
+        if elf.is_some() {
+            self.next_index += 1;
+        }
+        elf.map(Ok)
+    }
+}
+
+// Keeps the `n` elves carrying the most calories, ties going to the earlier elf
+struct TopN {
+    n: usize,
+    heap: BinaryHeap<Reverse<(u64, Reverse<usize>)>>,
+}
+
+impl TopN {
+    fn new(n: usize) -> Self {
+        Self {
+            n,
+            heap: BinaryHeap::new(),
+        }
+    }
+    fn push(&mut self, elf: &Elf) {
+        self.heap.push(Reverse((elf.total, Reverse(elf.index))));
+        if self.heap.len() > self.n {
+            self.heap.pop();
+        }
+    }
+    // (index, total) pairs, largest total first
+    fn into_sorted(self) -> Vec<(usize, u64)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((total, Reverse(index)))| (index, total))
+            .collect()
+    }
+}
+
+fn top_elves(reader: impl BufRead, n: usize) -> Result<Vec<(usize, u64)>, LedgerError> {
+    let mut top = TopN::new(n);
+    for elf in Elves::new(reader) {
+        top.push(&elf?);
+    }
+    Ok(top.into_sorted())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ReportFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => Err("Unknown report format, expected text, csv or json"),
+        }
+    }
+}
+
+// One line per elf in input order, with its rank by total calories
+fn report(reader: impl BufRead, format: ReportFormat) -> Result<String, LedgerError> {
+    let elves = Elves::new(reader).collect::<Result<Vec<Elf>, _>>()?;
+    let mut order = (0..elves.len()).collect::<Vec<usize>>();
+    order.sort_by_key(|&i| Reverse(elves[i].total));
+    let mut ranks = vec![0; elves.len()];
+    for (rank, i) in order.into_iter().enumerate() {
+        ranks[i] = rank + 1;
+    }
+
+    let rows = elves.iter().zip(ranks);
+    let lines = match format {
+        ReportFormat::Text => rows
+            .map(|(e, rank)| {
+                format!(
+                    "Elf {:>4}: {:>3} items, {:>8} calories, rank {}",
+                    e.index, e.items, e.total, rank
+                )
+            })
+            .collect::<Vec<String>>(),
+        ReportFormat::Csv => std::iter::once("index,items,total,rank".to_string())
+            .chain(rows.map(|(e, rank)| format!("{},{},{},{}", e.index, e.items, e.total, rank)))
+            .collect(),
+        ReportFormat::Json => {
+            let entries = rows
+                .map(|(e, rank)| {
+                    format!(
+                        "  {{\"index\": {}, \"items\": {}, \"total\": {}, \"rank\": {}}}",
+                        e.index, e.items, e.total, rank
+                    )
+                })
+                .collect::<Vec<String>>();
+            if entries.is_empty() {
+                vec!["[]".to_string()]
+            } else {
+                vec![format!("[\n{}\n]", entries.join(",\n"))]
+            }
+        }
+    };
+    Ok(lines.join("\n"))
+}
+
+fn part1(reader: impl BufRead) -> u64 {
+    top_elves(reader, 1)
+        .expect("Invalid input")
+        .first()
+        .map_or(0, |&(_, total)| total)
+}
+
+fn part2(reader: impl BufRead) -> u64 {
+    top_elves(reader, 3)
+        .expect("Invalid input")
+        .iter()
+        .map(|&(_, total)| total)
+        .sum()
+}
+
+fn open_input() -> BufReader<File> {
+    BufReader::new(File::open("inputs/day01.txt").expect("Could not read input file"))
+}
+
+// Options as (--top N, --report FORMAT), at most one of which is present
+type Options = (Option<usize>, Option<ReportFormat>);
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, &'static str> {
+    let mut args = args.into_iter();
+    let (mut top, mut format) = (None, None);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--top" => {
+                let n = args.next().ok_or("Expected a count after --top")?;
+                top = Some(n.parse().map_err(|_| "Invalid count after --top")?);
+            }
+            "--report" => {
+                let f = args.next().ok_or("Expected a format after --report")?;
+                format = Some(f.parse()?);
+            }
+            _ => return Err("Unknown argument, expected --top N or --report FORMAT"),
+        }
+    }
+    if top.is_some() && format.is_some() {
+        return Err("--top and --report cannot be combined");
+    }
+    Ok((top, format))
+}
 
 fn main() {
-    let input = fs::read_to_string("inputs/day01.txt").expect("Could not read input file");
-    let mut elves: Vec<i32> = input
-        .split("\n\n")
-        .map(|elf| elf.lines().flat_map(str::parse::<i32>).sum())
-        .collect();
-    elves.sort_by(|a, b| b.cmp(a));
-    let part1 = elves[0];
-    let part2: i32 = elves[..3].iter().sum();
+    let (top, format) = parse_args(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1)
+    });
+
+    let fail = |e: LedgerError| -> ! {
+        eprintln!("{e}");
+        process::exit(1)
+    };
+    if let Some(format) = format {
+        let report = report(open_input(), format).unwrap_or_else(|e| fail(e));
+        println!("{report}");
+        return;
+    }
+    if let Some(n) = top {
+        let top = top_elves(open_input(), n).unwrap_or_else(|e| fail(e));
+        top.iter()
+            .for_each(|(index, total)| println!("Elf {index}: {total}"));
+        return;
+    }
+
+    let part1 = part1(open_input());
+    let part2 = part2(open_input());
     println!("Part 1: {part1}\nPart 2: {part2}")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = "\
+1000
+2000
+3000
+
+4000
+
+5000
+6000
+
+7000
+8000
+9000
+
+10000";
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(TEST_INPUT.as_bytes()), 24000);
+    }
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(TEST_INPUT.as_bytes()), 45000);
+    }
+    #[test]
+    fn test_few_elves() {
+        assert_eq!(part2("100\n200\n\n300".as_bytes()), 600);
+        assert_eq!(part2("".as_bytes()), 0);
+        assert_eq!(
+            top_elves("5\n\n\n\n7\n".as_bytes(), 5).unwrap(),
+            vec![(2, 7), (1, 5)]
+        );
+    }
+    #[test]
+    fn test_large_totals() {
+        let input = format!("{}\n{}\n\n1", u32::MAX, u32::MAX);
+        assert_eq!(part1(input.as_bytes()), 2 * u32::MAX as u64);
+    }
+    #[test]
+    fn test_total_overflow() {
+        let input = format!("1\n\n{}\n1", u64::MAX);
+        let result = top_elves(input.as_bytes(), 1);
+        assert!(matches!(result, Err(LedgerError::Overflow(4))));
+        assert_eq!(top_elves("1".as_bytes(), usize::MAX).unwrap(), vec![(1, 1)]);
+    }
+    #[test]
+    fn test_invalid_number() {
+        let result = top_elves("1\n2\n\nx".as_bytes(), 3);
+        assert!(matches!(result, Err(LedgerError::InvalidNumber(4))));
+    }
+    #[test]
+    fn test_report() {
+        let csv = report(TEST_INPUT.as_bytes(), ReportFormat::Csv).unwrap();
+        assert_eq!(
+            csv,
+            "index,items,total,rank\n1,3,6000,4\n2,1,4000,5\n3,2,11000,2\n4,3,24000,1\n5,1,10000,3"
+        );
+        let json = report("1\n2\n\n5".as_bytes(), ReportFormat::Json).unwrap();
+        assert_eq!(
+            json,
+            "[\n  {\"index\": 1, \"items\": 2, \"total\": 3, \"rank\": 2},\n  {\"index\": 2, \"items\": 1, \"total\": 5, \"rank\": 1}\n]"
+        );
+        let text = report("1\n2".as_bytes(), ReportFormat::Text).unwrap();
+        assert_eq!(text, "Elf    1:   2 items,        3 calories, rank 1");
+    }
+    #[test]
+    fn test_parse_args() {
+        let args = |s: &str| parse_args(s.split_whitespace().map(String::from));
+        assert_eq!(args(""), Ok((None, None)));
+        assert_eq!(args("--top 5"), Ok((Some(5), None)));
+        assert_eq!(args("--report csv"), Ok((None, Some(ReportFormat::Csv))));
+        assert_eq!(
+            args("--top 5 --report csv"),
+            Err("--top and --report cannot be combined")
+        );
+        assert_eq!(
+            args("--tpo 5"),
+            Err("Unknown argument, expected --top N or --report FORMAT")
+        );
+        assert_eq!(
+            args("--report xml"),
+            Err("Unknown report format, expected text, csv or json")
+        );
+        assert_eq!(args("--top abc"), Err("Invalid count after --top"));
+        assert_eq!(args("--top"), Err("Expected a count after --top"));
+    }
+}
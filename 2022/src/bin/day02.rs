@@ -1,75 +1,268 @@
-use std::fs;
+use std::{env, fmt::Display, fs, process, str::FromStr};
 
-#[derive(Clone, Copy)]
-enum RPS {
-    Rock = 1,
-    Paper,
-    Scissor,
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Outcome {
+    Lose,
+    Draw,
+    Win,
 }
 
-impl RPS {
-    fn wins(&self, other: &Self) -> bool {
-        matches!(
-            (self, other),
-            (RPS::Rock, RPS::Scissor) | (RPS::Paper, RPS::Rock) | (RPS::Scissor, RPS::Paper)
-        )
-    }
-    // Returns the shape to choose to win over self
-    fn winning(&self) -> Self {
+impl Outcome {
+    const ALL: [Self; 3] = [Self::Lose, Self::Draw, Self::Win];
+
+    fn name(&self) -> &'static str {
         match self {
-            RPS::Rock => RPS::Paper,
-            RPS::Paper => RPS::Scissor,
-            RPS::Scissor => RPS::Rock,
+            Self::Lose => "lose",
+            Self::Draw => "draw",
+            Self::Win => "win",
         }
     }
-    fn from_char(c: char) -> Option<Self> {
-        match c {
-            'A' | 'X' => Some(Self::Rock),
-            'B' | 'Y' => Some(Self::Paper),
-            'C' | 'Z' => Some(Self::Scissor),
+}
+
+impl FromStr for Outcome {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|o| o.name().eq_ignore_ascii_case(s))
+            .ok_or("Invalid outcome")
+    }
+}
+
+// A hand game with an odd number of shapes ranked in a cycle, where every shape
+// beats the (n - 1) / 2 shapes preceding it and loses to the ones following it.
+#[derive(Clone, Debug)]
+struct Game {
+    shapes: Vec<&'static str>,
+    shape_scores: Vec<u32>,
+    outcome_scores: [u32; 3], // lose, draw, win
+}
+
+impl Game {
+    // Shapes score 1, 2, 3, ... in order, outcomes score 0, 3 and 6
+    fn new(shapes: &[&'static str]) -> Result<Self, &'static str> {
+        let scores = (1..=shapes.len() as u32).collect();
+        Self::with_scores(shapes, scores, [0, 3, 6])
+    }
+
+    fn with_scores(
+        shapes: &[&'static str],
+        shape_scores: Vec<u32>,
+        outcome_scores: [u32; 3],
+    ) -> Result<Self, &'static str> {
+        if shapes.len() < 3 || shapes.len().is_multiple_of(2) {
+            return Err("A cyclic game needs an odd number of at least 3 shapes");
+        }
+        if shapes.len() > 13 {
+            return Err("Too many shapes to encode with letters");
+        }
+        if shape_scores.len() != shapes.len() {
+            return Err("Every shape needs a score");
+        }
+        Ok(Self {
+            shapes: shapes.to_vec(),
+            shape_scores,
+            outcome_scores,
+        })
+    }
+
+    fn rock_paper_scissors() -> Self {
+        Self::new(&["Rock", "Paper", "Scissors"]).unwrap()
+    }
+
+    fn rock_paper_scissors_lizard_spock() -> Self {
+        Self::new(&["Rock", "Spock", "Paper", "Lizard", "Scissors"]).unwrap()
+    }
+
+    fn rock_paper_scissors_7() -> Self {
+        Self::new(&[
+            "Water", "Air", "Paper", "Sponge", "Scissors", "Fire", "Rock",
+        ])
+        .unwrap()
+    }
+
+    fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "rps" => Some(Self::rock_paper_scissors()),
+            "rpsls" => Some(Self::rock_paper_scissors_lizard_spock()),
+            "rps7" => Some(Self::rock_paper_scissors_7()),
             _ => None,
         }
     }
+
+    fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    fn outcome(&self, ply: usize, opp: usize) -> Outcome {
+        let n = self.len();
+        match (ply + n - opp) % n {
+            0 => Outcome::Draw,
+            d if d <= n / 2 => Outcome::Win,
+            _ => Outcome::Lose,
+        }
+    }
+
+    // Shape to play against `opp` for the given outcome, preferring the
+    // highest scoring one when several shapes would do
+    fn shape_for(&self, opp: usize, outcome: Outcome) -> usize {
+        (0..self.len())
+            .filter(|&ply| self.outcome(ply, opp) == outcome)
+            .max_by_key(|&ply| (self.shape_scores[ply], std::cmp::Reverse(ply)))
+            .expect("Every outcome is reachable in a cyclic game")
+    }
+
+    fn shape(&self, name: &str) -> Option<usize> {
+        self.shapes
+            .iter()
+            .position(|s| s.eq_ignore_ascii_case(name))
+    }
 }
 
-enum Strategy {
-    Win,
-    Draw,
-    Lose,
+// How the second column of the strategy guide is read
+#[derive(Clone, Debug, PartialEq)]
+enum Column {
+    Shapes(Vec<(char, usize)>),
+    Outcomes(Vec<(char, Outcome)>),
 }
 
-impl Strategy {
-    fn from_char(c: char) -> Option<Self> {
-        match c {
-            'X' => Some(Self::Lose),
-            'Y' => Some(Self::Draw),
-            'Z' => Some(Self::Win),
-            _ => None,
+// Letter mappings for both columns of the strategy guide
+#[derive(Clone, Debug, PartialEq)]
+struct Encoding {
+    opponent: Vec<(char, usize)>,
+    player: Column,
+}
+
+impl Encoding {
+    // The opponent's column uses letters from A onwards, ours ends at Z
+    fn shapes(game: &Game) -> Self {
+        let first = (b'Z' + 1 - game.len() as u8) as char;
+        Self {
+            opponent: Self::letters('A', game.len()),
+            player: Column::Shapes(Self::letters(first, game.len())),
         }
     }
+
+    // X, Y and Z are the outcome of the round
+    fn outcomes(game: &Game) -> Self {
+        Self {
+            opponent: Self::letters('A', game.len()),
+            player: Column::Outcomes(vec![
+                ('X', Outcome::Lose),
+                ('Y', Outcome::Draw),
+                ('Z', Outcome::Win),
+            ]),
+        }
+    }
+
+    fn letters(first: char, n: usize) -> Vec<(char, usize)> {
+        (0..n)
+            .map(|i| ((first as u8 + i as u8) as char, i))
+            .collect()
+    }
+
+    // Reads a config of the form
+    //   opponent: A=Rock B=Paper C=Scissors
+    //   player: X=Rock Y=Paper Z=Scissors   (or X=lose Y=draw Z=win)
+    // with shape names taken from the game
+    fn parse(game: &Game, config: &str) -> Result<Self, &'static str> {
+        let mut opponent = None;
+        let mut player = None;
+        for line in config.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (column, mappings) = line.split_once(':').ok_or("Missing column name")?;
+            let mappings = mappings
+                .split_whitespace()
+                .map(|m| {
+                    let (letter, value) =
+                        m.split_once('=').ok_or("Mapping must be LETTER=VALUE")?;
+                    let mut chars = letter.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Ok((c, value)),
+                        _ => Err("Mapping key must be a single letter"),
+                    }
+                })
+                .collect::<Result<Vec<(char, &str)>, _>>()?;
+            if (1..mappings.len()).any(|i| mappings[..i].iter().any(|m| m.0 == mappings[i].0)) {
+                return Err("Duplicate mapping key");
+            }
+            let shapes = || {
+                mappings
+                    .iter()
+                    .map(|&(c, name)| Ok((c, game.shape(name).ok_or("Unknown shape")?)))
+                    .collect::<Result<Vec<(char, usize)>, &'static str>>()
+            };
+            match column.trim() {
+                "opponent" => opponent = Some(shapes()?),
+                "player" => {
+                    player = Some(match mappings.first() {
+                        Some((_, value)) if value.parse::<Outcome>().is_ok() => Column::Outcomes(
+                            mappings
+                                .iter()
+                                .map(|&(c, o)| Ok((c, o.parse()?)))
+                                .collect::<Result<_, &'static str>>()?,
+                        ),
+                        _ => Column::Shapes(shapes()?),
+                    })
+                }
+                _ => return Err("Unknown column, expected opponent or player"),
+            }
+        }
+        let encoding = Self {
+            opponent: opponent.ok_or("Missing opponent mapping")?,
+            player: player.ok_or("Missing player mapping")?,
+        };
+        // Every shape the opponent can play, and every shape or outcome of
+        // ours, needs a letter for the guide to be readable
+        let covers =
+            |mapping: &[(char, usize)]| (0..game.len()).all(|s| mapping.iter().any(|m| m.1 == s));
+        if !covers(&encoding.opponent) {
+            return Err("Opponent mapping must cover every shape");
+        }
+        let player_covered = match &encoding.player {
+            Column::Shapes(m) => covers(m),
+            Column::Outcomes(m) => Outcome::ALL.iter().all(|&o| m.iter().any(|x| x.1 == o)),
+        };
+        if !player_covered {
+            return Err("Player mapping must cover every shape or every outcome");
+        }
+        Ok(encoding)
+    }
+
+    fn describe(&self, game: &Game) -> String {
+        match &self.player {
+            Column::Shapes(m) => m
+                .iter()
+                .map(|&(c, s)| format!("{}={}", c, game.shapes[s]))
+                .collect::<Vec<String>>()
+                .join(" "),
+            Column::Outcomes(m) => m
+                .iter()
+                .map(|&(c, o)| format!("{}={}", c, o.name()))
+                .collect::<Vec<String>>()
+                .join(" "),
+        }
+    }
+}
+
+fn lookup<T: Copy>(mapping: &[(char, T)], c: char) -> Option<T> {
+    mapping.iter().find(|&&(l, _)| l == c).map(|&(_, v)| v)
 }
 
 struct Round {
-    ply: RPS,
-    opp: RPS,
+    ply: usize,
+    opp: usize,
 }
 
 impl Round {
-    fn from_line_part1(line: &str) -> Option<Self> {
+    fn from_line(game: &Game, encoding: &Encoding, line: &str) -> Option<Self> {
         let (opp, ply) = Self::round_chars(line)?;
-        Some(Self {
-            ply: RPS::from_char(ply)?,
-            opp: RPS::from_char(opp)?,
-        })
-    }
-    fn from_line_part2(line: &str) -> Option<Self> {
-        let (opp, strategy) = Self::round_chars(line)?;
-        let opp = RPS::from_char(opp)?;
-        let strategy = Strategy::from_char(strategy)?;
-        let ply = match strategy {
-            Strategy::Win => opp.winning(),
-            Strategy::Draw => opp,
-            Strategy::Lose => opp.winning().winning(),
+        let opp = lookup(&encoding.opponent, opp)?;
+        let ply = match &encoding.player {
+            Column::Shapes(m) => lookup(m, ply)?,
+            Column::Outcomes(m) => game.shape_for(opp, lookup(m, ply)?),
         };
         Some(Self { ply, opp })
     }
@@ -78,36 +271,144 @@ impl Round {
         Some((round.next()?.next()?, round.next()?.next()?))
     }
     // Score gained from playing one round
-    fn score(self) -> u32 {
-        let score = match (self.ply, self.opp) {
-            (ply, opp) if ply.wins(&opp) => 6,
-            (ply, opp) if opp.wins(&ply) => 0,
-            _ => 3,
-        };
-        score + self.ply as u32
+    fn score(&self, game: &Game) -> u32 {
+        let outcome = game.outcome(self.ply, self.opp);
+        game.outcome_scores[outcome as usize] + game.shape_scores[self.ply]
     }
 }
 
-fn part1(input: &str) -> u32 {
+// A round whose letters the encoding can't decode
+#[derive(Debug, PartialEq)]
+struct GuideError {
+    line: usize, // 1-based
+}
+
+impl Display for GuideError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cannot decode the round on line {}", self.line)
+    }
+}
+
+fn total_score(game: &Game, encoding: &Encoding, input: &str) -> Result<u32, GuideError> {
     input
         .lines()
-        .flat_map(Round::from_line_part1)
-        .map(Round::score)
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            Round::from_line(game, encoding, line)
+                .map(|round| round.score(game))
+                .ok_or(GuideError { line: i + 1 })
+        })
         .sum()
 }
 
-fn part2(input: &str) -> u32 {
-    input
+// All ordered selections of k distinct shapes out of n
+fn permutations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![vec![]];
+    }
+    let mut result = vec![];
+    for prefix in permutations(n, k - 1) {
+        for s in (0..n).filter(|s| !prefix.contains(s)) {
+            let mut p = prefix.clone();
+            p.push(s);
+            result.push(p);
+        }
+    }
+    result
+}
+
+// Scores every possible reading of the second column: each assignment of its
+// letters to distinct shapes, and the outcome reading. Best reading first,
+// leaving out readings that can't decode every round.
+fn analyse(game: &Game, input: &str) -> Vec<(Encoding, u32)> {
+    let mut letters = input
         .lines()
-        .flat_map(Round::from_line_part2)
-        .map(Round::score)
-        .sum()
+        .flat_map(Round::round_chars)
+        .map(|(_, c)| c)
+        .collect::<Vec<char>>();
+    letters.sort_unstable();
+    letters.dedup();
+
+    let opponent = Encoding::shapes(game).opponent;
+    let mut readings = permutations(game.len(), letters.len().min(game.len()))
+        .into_iter()
+        .map(|shapes| Encoding {
+            opponent: opponent.clone(),
+            player: Column::Shapes(letters.iter().copied().zip(shapes).collect()),
+        })
+        .collect::<Vec<Encoding>>();
+    readings.push(Encoding::outcomes(game));
+
+    let mut scores = readings
+        .into_iter()
+        .filter_map(|encoding| {
+            let score = total_score(game, &encoding, input).ok()?;
+            Some((encoding, score))
+        })
+        .collect::<Vec<(Encoding, u32)>>();
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    scores
+}
+
+fn part1(game: &Game, input: &str) -> u32 {
+    total_score(game, &Encoding::shapes(game), input).unwrap_or_else(|e| panic!("{e}"))
+}
+
+fn part2(game: &Game, input: &str) -> u32 {
+    total_score(game, &Encoding::outcomes(game), input).unwrap_or_else(|e| panic!("{e}"))
+}
+
+fn fail(message: impl Display) -> ! {
+    eprintln!("{message}");
+    process::exit(1)
 }
 
 fn main() {
+    let mut game = Game::rock_paper_scissors();
+    let mut encoding: Option<String> = None;
+    let mut analysis = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--encoding" => {
+                let path = args
+                    .next()
+                    .unwrap_or_else(|| fail("Expected a path after --encoding"));
+                encoding = Some(path);
+            }
+            "--analyse" => analysis = true,
+            name => {
+                game = Game::by_name(name).unwrap_or_else(|| {
+                    fail(
+                        "Unknown argument, expected rps, rpsls, rps7, --encoding PATH or --analyse",
+                    )
+                })
+            }
+        }
+    }
+
     let input = fs::read_to_string("inputs/day02.txt").expect("Could not read input file");
-    let part1 = part1(&input);
-    let part2 = part2(&input);
+    if let Some(path) = encoding {
+        let config = fs::read_to_string(path).expect("Could not read encoding file");
+        let encoding = Encoding::parse(&game, &config).unwrap_or_else(|e| fail(e));
+        let score = total_score(&game, &encoding, &input).unwrap_or_else(|e| fail(e));
+        println!("Score: {score}");
+        return;
+    }
+    if analysis {
+        let scores = analyse(&game, &input);
+        if scores.is_empty() {
+            fail("No reading decodes every round of the guide");
+        }
+        for (encoding, score) in scores {
+            println!("{:>8}  {}", score, encoding.describe(&game));
+        }
+        return;
+    }
+
+    let part1 = part1(&game, &input);
+    let part2 = part2(&game, &input);
     println!("Part 1: {part1}\nPart 2: {part2}");
 }
 
@@ -120,7 +421,7 @@ mod tests {
         A Y
         B X
         C Z"#;
-        let score = part1(&input);
+        let score = part1(&Game::rock_paper_scissors(), input);
         assert_eq!(score, 15);
     }
     #[test]
@@ -129,7 +430,144 @@ mod tests {
         A Y
         B X
         C Z"#;
-        let score = part2(&input);
+        let score = part2(&Game::rock_paper_scissors(), input);
         assert_eq!(score, 12);
     }
+    #[test]
+    fn test_rock_paper_scissors_lizard_spock() {
+        let game = Game::rock_paper_scissors_lizard_spock();
+        let name = |s: usize| game.shapes[s];
+        for (winner, loser) in [
+            ("Scissors", "Paper"),
+            ("Paper", "Rock"),
+            ("Rock", "Lizard"),
+            ("Lizard", "Spock"),
+            ("Spock", "Scissors"),
+            ("Scissors", "Lizard"),
+            ("Lizard", "Paper"),
+            ("Paper", "Spock"),
+            ("Spock", "Rock"),
+            ("Rock", "Scissors"),
+        ] {
+            let w = (0..5).find(|&s| name(s) == winner).unwrap();
+            let l = (0..5).find(|&s| name(s) == loser).unwrap();
+            assert_eq!(game.outcome(w, l), Outcome::Win);
+            assert_eq!(game.outcome(l, w), Outcome::Lose);
+        }
+        // Rock vs Spock, Paper vs Lizard, Scissors vs Scissors
+        let input = "B V\nD X\nE Z";
+        assert_eq!(part1(&game, input), 1 + 3 + 8);
+        // Win against Rock and lose against Lizard, both with Paper as the higher scoring choice
+        let input = "A Z\nD X";
+        assert_eq!(part2(&game, input), 9 + 3);
+    }
+    #[test]
+    fn test_rock_paper_scissors_7() {
+        let game = Game::by_name("rps7").unwrap();
+        let rock = 6;
+        let beaten = (0..7)
+            .filter(|&s| game.outcome(rock, s) == Outcome::Win)
+            .map(|s| game.shapes[s])
+            .collect::<Vec<_>>();
+        assert_eq!(beaten, vec!["Sponge", "Scissors", "Fire"]);
+        // Water vs Rock wins, Rock vs Water loses
+        assert_eq!(part1(&game, "G T\nA Z"), 7 + 7);
+        // Drawing against Air plays Air
+        assert_eq!(part2(&game, "B Y"), 3 + 2);
+    }
+    #[test]
+    fn test_custom_scores() {
+        let game =
+            Game::with_scores(&["Rock", "Paper", "Scissors"], vec![10, 20, 30], [1, 2, 3]).unwrap();
+        assert_eq!(part1(&game, "A Y\nB X\nC Z"), 23 + 11 + 32);
+        assert!(Game::new(&["Rock", "Paper"]).is_err());
+        assert!(Game::new(&["A", "B", "C", "D"]).is_err());
+        assert!(Game::with_scores(&["A", "B", "C"], vec![1], [0, 3, 6]).is_err());
+    }
+    #[test]
+    fn test_encoding_config() {
+        let game = Game::rock_paper_scissors();
+        let config = "\
+# Opponent letters are shifted by one
+opponent: A=Paper B=Scissors C=Rock
+player: X=Scissors Y=Rock Z=Paper";
+        let encoding = Encoding::parse(&game, config).unwrap();
+        // Paper vs Rock, Scissors vs Scissors, Rock vs Paper
+        assert_eq!(
+            total_score(&game, &encoding, "A Y\nB X\nC Z"),
+            Ok(1 + 6 + 8)
+        );
+
+        let config = "opponent: A=Rock B=Paper C=Scissors\nplayer: X=lose Y=draw Z=win";
+        assert_eq!(
+            Encoding::parse(&game, config),
+            Ok(Encoding::outcomes(&game))
+        );
+        assert!(Encoding::parse(&game, "opponent: A=Rock").is_err());
+        assert!(Encoding::parse(&game, "opponent: A=Stone\nplayer: X=Rock").is_err());
+        assert!(Encoding::parse(&game, "opponent: AB=Rock\nplayer: X=Rock").is_err());
+        let opponent = "opponent: A=Rock B=Paper C=Scissors\n";
+        let parse = |player: &str| Encoding::parse(&game, &format!("{opponent}{player}"));
+        assert_eq!(
+            parse("player: X=Rock X=Paper Z=Scissors"),
+            Err("Duplicate mapping key")
+        );
+        assert_eq!(
+            parse("player: X=Rock Y=Paper Z=Rock"),
+            Err("Player mapping must cover every shape or every outcome")
+        );
+        assert_eq!(
+            parse("player: X=lose Y=draw"),
+            Err("Player mapping must cover every shape or every outcome")
+        );
+        assert_eq!(
+            Encoding::parse(
+                &game,
+                "opponent: A=Rock B=Paper\nplayer: X=lose Y=draw Z=win"
+            ),
+            Err("Opponent mapping must cover every shape")
+        );
+    }
+    #[test]
+    fn test_undecodable_round() {
+        let game = Game::rock_paper_scissors();
+        let shapes = Encoding::shapes(&game);
+        assert_eq!(
+            total_score(&game, &shapes, "A Y\n\nB W\nC Z"),
+            Err(GuideError { line: 3 })
+        );
+        assert_eq!(
+            total_score(&game, &shapes, "A Y\nD X"),
+            Err(GuideError { line: 2 })
+        );
+        assert_eq!(
+            total_score(&game, &shapes, "A\n"),
+            Err(GuideError { line: 1 })
+        );
+        // Only the outcome reading fails on letters outside X, Y and Z
+        let scores = analyse(&game, "A V\nB W");
+        assert_eq!(scores.len(), 6);
+        assert!(scores
+            .iter()
+            .all(|(e, _)| matches!(e.player, Column::Shapes(_))));
+    }
+    #[test]
+    fn test_analyse() {
+        let game = Game::rock_paper_scissors();
+        let input = "A Y\nB X\nC Z";
+        let scores = analyse(&game, input);
+        assert_eq!(scores.len(), 7);
+        // Playing each letter as the shape that beats the opponent: X=Scissors, Y=Paper, Z=Rock
+        let (best, score) = &scores[0];
+        assert_eq!(best.describe(&game), "X=Scissors Y=Paper Z=Rock");
+        assert_eq!(*score, 8 + 9 + 7);
+        let score_of = |reading: &str| {
+            scores
+                .iter()
+                .find(|(e, _)| e.describe(&game) == reading)
+                .map(|&(_, s)| s)
+        };
+        assert_eq!(score_of("X=Rock Y=Paper Z=Scissors"), Some(15));
+        assert_eq!(score_of("X=lose Y=draw Z=win"), Some(12));
+    }
 }
@@ -1,44 +1,169 @@
-use std::{collections::HashSet, fs, iter::FromIterator};
+use std::{env, fmt::Display, fs};
 
-fn char_priority(c: &char) -> u32 {
-    // Will panic on invalid input
-    if c.is_lowercase() {
-        *c as u32 - 'a' as u32 + 1
+#[derive(Debug, PartialEq)]
+enum RucksackError {
+    InvalidItem { line: usize, item: char },
+    InvalidGroupSize(usize),
+    InvalidCompartments(usize),
+    UnevenGroups { rucksacks: usize, group_size: usize },
+    UnevenCompartments { line: usize, compartments: usize },
+    NoBadge { group: usize },
+    MultipleBadges { group: usize, badges: String },
+}
+
+impl Display for RucksackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidItem { line, item } => {
+                write!(f, "Invalid item {:?} on line {}", item, line)
+            }
+            Self::InvalidGroupSize(size) => write!(f, "Invalid group size {}", size),
+            Self::InvalidCompartments(n) => write!(f, "Invalid compartment count {}", n),
+            Self::UnevenGroups {
+                rucksacks,
+                group_size,
+            } => write!(
+                f,
+                "{} rucksacks cannot be split into groups of {}",
+                rucksacks, group_size
+            ),
+            Self::UnevenCompartments { line, compartments } => write!(
+                f,
+                "Rucksack on line {} cannot be split into {} compartments",
+                line, compartments
+            ),
+            Self::NoBadge { group } => write!(f, "Group {} has no common badge", group),
+            Self::MultipleBadges { group, badges } => {
+                write!(f, "Group {} has multiple common badges: {}", group, badges)
+            }
+        }
+    }
+}
+
+fn priority(item: char) -> Option<u32> {
+    match item {
+        'a'..='z' => Some(item as u32 - 'a' as u32 + 1),
+        'A'..='Z' => Some(item as u32 - 'A' as u32 + 27),
+        _ => None,
+    }
+}
+
+fn item(priority: u32) -> char {
+    if priority <= 26 {
+        (b'a' + priority as u8 - 1) as char
     } else {
-        *c as u32 - 'A' as u32 + 27
+        (b'A' + priority as u8 - 27) as char
     }
 }
 
-fn part1(input: &str) -> u32 {
+// Set of item types, bit n being set for the item with priority n
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    const ALL: Self = Self(((1 << 52) - 1) << 1);
+
+    fn from_items(items: &[char], line: usize) -> Result<Self, RucksackError> {
+        items.iter().try_fold(Self(0), |set, &c| {
+            let p = priority(c).ok_or(RucksackError::InvalidItem { line, item: c })?;
+            Ok(Self(set.0 | 1 << p))
+        })
+    }
+    fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+    fn len(&self) -> u32 {
+        self.0.count_ones()
+    }
+    fn priorities(self) -> impl Iterator<Item = u32> {
+        (1..=52).filter(move |p| self.0 & 1 << p != 0)
+    }
+}
+
+// Items present in every one of the sets
+fn common(sets: impl IntoIterator<Item = ItemSet>) -> ItemSet {
+    sets.into_iter().fold(ItemSet::ALL, ItemSet::intersection)
+}
+
+// Non-empty rucksacks with their 1-based line numbers
+fn rucksacks(input: &str) -> impl Iterator<Item = (usize, Vec<char>)> + '_ {
     input
         .lines()
-        .map(str::trim)
-        .map(|l| l.split_at(l.len() / 2))
-        .map(|(l, r)| {
-            let left = HashSet::<char>::from_iter(l.chars());
-            let right = HashSet::<char>::from_iter(r.chars());
-            left.intersection(&right).map(char_priority).sum::<u32>()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim().chars().collect::<Vec<char>>()))
+        .filter(|(_, l)| !l.is_empty())
+}
+
+// Sum of priorities of the items shared by every compartment of each rucksack
+fn compartment_priorities(input: &str, compartments: usize) -> Result<u32, RucksackError> {
+    if compartments == 0 {
+        return Err(RucksackError::InvalidCompartments(compartments));
+    }
+    rucksacks(input).try_fold(0, |sum, (line, items)| {
+        if items.len() % compartments != 0 {
+            return Err(RucksackError::UnevenCompartments { line, compartments });
+        }
+        let sets = items
+            .chunks(items.len() / compartments)
+            .map(|c| ItemSet::from_items(c, line))
+            .collect::<Result<Vec<ItemSet>, _>>()?;
+        Ok(sum + common(sets).priorities().sum::<u32>())
+    })
+}
+
+// Sum of priorities of the single badge shared by each group of rucksacks
+fn badge_priorities(input: &str, group_size: usize) -> Result<u32, RucksackError> {
+    if group_size == 0 {
+        return Err(RucksackError::InvalidGroupSize(group_size));
+    }
+    let sets = rucksacks(input)
+        .map(|(line, items)| ItemSet::from_items(&items, line))
+        .collect::<Result<Vec<ItemSet>, _>>()?;
+    if sets.len() % group_size != 0 {
+        return Err(RucksackError::UnevenGroups {
+            rucksacks: sets.len(),
+            group_size,
+        });
+    }
+    sets.chunks(group_size)
+        .enumerate()
+        .try_fold(0, |sum, (i, group)| {
+            let badges = common(group.iter().copied());
+            match badges.len() {
+                0 => Err(RucksackError::NoBadge { group: i + 1 }),
+                1 => Ok(sum + badges.priorities().sum::<u32>()),
+                _ => Err(RucksackError::MultipleBadges {
+                    group: i + 1,
+                    badges: badges.priorities().map(item).collect(),
+                }),
+            }
         })
-        .sum::<u32>()
+}
+
+fn part1(input: &str) -> u32 {
+    compartment_priorities(input, 2).unwrap_or_else(|e| panic!("{e}"))
 }
 
 fn part2(input: &str) -> u32 {
-    let lines = input.trim().lines().map(str::trim).collect::<Vec<&str>>();
-    lines
-        .chunks(3)
-        .map(|group| {
-            // Panics if input lines were not evenly divisible by 3
-            let a = HashSet::<char>::from_iter(group[0].chars());
-            let b = HashSet::<char>::from_iter(group[1].chars());
-            let c = HashSet::<char>::from_iter(group[2].chars());
-            let ab = HashSet::<char>::from_iter(a.intersection(&b).cloned());
-            ab.intersection(&c).map(char_priority).sum::<u32>()
-        })
-        .sum::<u32>()
+    badge_priorities(input, 3).unwrap_or_else(|e| panic!("{e}"))
 }
 
 fn main() {
     let input = fs::read_to_string("inputs/day03.txt").expect("Could not read input file");
+    let mut args = env::args().skip(1).map(|a| a.parse::<usize>());
+    if let (Some(compartments), Some(group_size)) = (args.next(), args.next()) {
+        let compartments = compartments.expect("Invalid compartment count");
+        let group_size = group_size.expect("Invalid group size");
+        match compartment_priorities(&input, compartments) {
+            Ok(sum) => println!("Compartments: {sum}"),
+            Err(e) => println!("Compartments: {e}"),
+        }
+        match badge_priorities(&input, group_size) {
+            Ok(sum) => println!("Badges: {sum}"),
+            Err(e) => println!("Badges: {e}"),
+        }
+        return;
+    }
     let part1 = part1(&input);
     let part2 = part2(&input);
     println!("Part 1: {part1}\nPart 2: {part2}");
@@ -57,7 +182,7 @@ mod tests {
         wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
         ttgJtRGJQctTZtZT
         CrZsJsPPZsGzwwsLwLmpwMDw"#;
-        let score = part1(&input);
+        let score = part1(input);
         assert_eq!(score, 157);
     }
     #[test]
@@ -69,7 +194,55 @@ mod tests {
         wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
         ttgJtRGJQctTZtZT
         CrZsJsPPZsGzwwsLwLmpwMDw"#;
-        let score = part2(&input);
+        let score = part2(input);
         assert_eq!(score, 70);
     }
+    #[test]
+    fn test_other_sizes() {
+        // Three compartments sharing only c, then groups of two sharing Z and y
+        assert_eq!(compartment_priorities("abcxycpqc", 3), Ok(3));
+        assert_eq!(badge_priorities("aZ\nbZ\nxy\nyz", 2), Ok(52 + 25));
+        assert_eq!(
+            badge_priorities("ab\nab", 1),
+            Err(RucksackError::MultipleBadges {
+                group: 1,
+                badges: "ab".to_string()
+            })
+        );
+    }
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            badge_priorities("ab\nbc", 3),
+            Err(RucksackError::UnevenGroups {
+                rucksacks: 2,
+                group_size: 3
+            })
+        );
+        assert_eq!(
+            badge_priorities("ab", 0),
+            Err(RucksackError::InvalidGroupSize(0))
+        );
+        assert_eq!(
+            badge_priorities("ab\ncd", 2),
+            Err(RucksackError::NoBadge { group: 1 })
+        );
+        assert_eq!(
+            badge_priorities("ab\nac\nbc\nb1", 2),
+            Err(RucksackError::InvalidItem { line: 4, item: '1' })
+        );
+        assert_eq!(
+            compartment_priorities("abc", 2),
+            Err(RucksackError::UnevenCompartments {
+                line: 1,
+                compartments: 2
+            })
+        );
+        assert_eq!(
+            compartment_priorities("aé", 2),
+            Err(RucksackError::InvalidItem {
+                line: 1, item: 'é'
+            })
+        );
+    }
 }
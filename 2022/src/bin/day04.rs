@@ -1,30 +1,200 @@
-use std::fs;
+use std::{env, fs, str::FromStr};
 
-fn parse_line(line: &str) -> ((u32, u32), (u32, u32)) {
-    let nums: Vec<u32> = line.trim().split(&[',', '-']).flat_map(str::parse).collect();
-    ((nums[0], nums[1]), (nums[2], nums[3]))
+// Inclusive range of section IDs
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Sections {
+    start: u32,
+    end: u32,
 }
 
-fn parse_input(input: &str) -> impl Iterator<Item = ((u32, u32), (u32, u32))> + '_ {
-    input.trim().lines().map(parse_line)
+impl FromStr for Sections {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once('-').ok_or("Missing '-' in section range")?;
+        let start = start.trim().parse().map_err(|_| "Invalid section start")?;
+        let end = end.trim().parse().map_err(|_| "Invalid section end")?;
+        if start > end {
+            return Err("Section range ends before it starts");
+        }
+        Ok(Self { start, end })
+    }
 }
 
-fn part1(input: &str) -> usize {
+impl Sections {
+    // Number of sections in both ranges, which can be 2^32 for two full ranges
+    fn shared(&self, other: &Self) -> u64 {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        (end as u64 + 1).saturating_sub(start as u64)
+    }
+}
+
+// Allen's interval relations of the first range to the second. Sections are whole
+// units, so a range a-b covers [a, b + 1) and 4-5 "meets" 6-7.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Relation {
+    Before,
+    Meets,
+    Overlaps,
+    Starts,
+    During,
+    Finishes,
+    Equals,
+    FinishedBy,
+    Contains,
+    StartedBy,
+    OverlappedBy,
+    MetBy,
+    After,
+}
+
+impl Relation {
+    const ALL: [Self; 13] = [
+        Self::Before,
+        Self::Meets,
+        Self::Overlaps,
+        Self::Starts,
+        Self::During,
+        Self::Finishes,
+        Self::Equals,
+        Self::FinishedBy,
+        Self::Contains,
+        Self::StartedBy,
+        Self::OverlappedBy,
+        Self::MetBy,
+        Self::After,
+    ];
+
+    fn between(a: &Sections, b: &Sections) -> Self {
+        use std::cmp::Ordering::*;
+        if (a.start, a.end) > (b.start, b.end) {
+            return Self::between(b, a).inverse();
+        }
+        // From here on a starts first, or both start together and a ends first
+        match (a.start.cmp(&b.start), a.end.cmp(&b.end)) {
+            _ if a.end.checked_add(1).is_some_and(|e| e < b.start) => Self::Before,
+            _ if a.end.checked_add(1) == Some(b.start) => Self::Meets,
+            (Equal, Equal) => Self::Equals,
+            (Equal, _) => Self::Starts,
+            (_, Less) => Self::Overlaps,
+            (_, Equal) => Self::FinishedBy,
+            (_, Greater) => Self::Contains,
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        Self::ALL[Self::ALL.len() - 1 - *self as usize]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Before => "before",
+            Self::Meets => "meets",
+            Self::Overlaps => "overlaps",
+            Self::Starts => "starts",
+            Self::During => "during",
+            Self::Finishes => "finishes",
+            Self::Equals => "equals",
+            Self::FinishedBy => "finished by",
+            Self::Contains => "contains",
+            Self::StartedBy => "started by",
+            Self::OverlappedBy => "overlapped by",
+            Self::MetBy => "met by",
+            Self::After => "after",
+        }
+    }
+
+    // One of the ranges lies completely within the other
+    fn is_containment(&self) -> bool {
+        matches!(
+            self,
+            Self::Starts
+                | Self::During
+                | Self::Finishes
+                | Self::Equals
+                | Self::FinishedBy
+                | Self::Contains
+                | Self::StartedBy
+        )
+    }
+
+    // The ranges share at least one section
+    fn is_overlapping(&self) -> bool {
+        !matches!(self, Self::Before | Self::Meets | Self::MetBy | Self::After)
+    }
+}
+
+fn parse_line(line: &str) -> Result<(Sections, Sections), &'static str> {
+    let (a, b) = line
+        .trim()
+        .split_once(',')
+        .ok_or("Missing ',' between ranges")?;
+    Ok((a.parse()?, b.parse()?))
+}
+
+fn parse_input(input: &str) -> Result<Vec<(Sections, Sections)>, String> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| parse_line(line).map_err(|e| format!("Line {}: {}", i + 1, e)))
+        .collect()
+}
+
+fn relations(input: &str) -> impl Iterator<Item = Relation> {
     parse_input(input)
-        .map(|((l1, r1), (l2, r2))| (l1 <= l2 && r1 >= r2) || (l2 <= l1 && r2 >= r1))
-        .filter(|e| *e)
-        .count()
+        .unwrap_or_else(|e| panic!("{e}"))
+        .into_iter()
+        .map(|(a, b)| Relation::between(&a, &b))
 }
 
-fn part2(input: &str) -> usize {
+// Number of pairs in each relation, in the order of `Relation::ALL`
+fn histogram(input: &str) -> [usize; 13] {
+    let mut counts = [0; 13];
+    relations(input).for_each(|r| counts[r as usize] += 1);
+    counts
+}
+
+fn report(input: &str) -> String {
+    let counts = histogram(input);
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    Relation::ALL
+        .iter()
+        .zip(counts)
+        .map(|(r, count)| {
+            let bar = "#".repeat((count * 40).div_ceil(max));
+            format!("{:>13} {:>5} {}", r.name(), count, bar)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Total number of sections assigned to both elves, summed over all pairs
+fn double_covered(input: &str) -> u64 {
     parse_input(input)
-        .map(|((l1, r1), (l2, r2))| l1 <= r2 && l2 <= r1)
-        .filter(|e| *e)
-        .count()
+        .unwrap_or_else(|e| panic!("{e}"))
+        .iter()
+        .map(|(a, b)| a.shared(b))
+        .sum()
+}
+
+fn part1(input: &str) -> usize {
+    relations(input).filter(Relation::is_containment).count()
+}
+
+fn part2(input: &str) -> usize {
+    relations(input).filter(Relation::is_overlapping).count()
 }
 
 fn main() {
     let input = fs::read_to_string("inputs/day04.txt").expect("Could not read input file");
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--report" => println!("{}", report(&input)),
+            "--double-covered" => println!("Double covered: {}", double_covered(&input)),
+            _ => {}
+        }
+    }
     let part1 = part1(&input);
     let part2 = part2(&input);
     println!("Part 1: {part1}");
@@ -44,7 +214,7 @@ mod tests {
         2-8,3-7
         6-6,4-6
         2-6,4-8"#;
-        let score = part1(&input);
+        let score = part1(input);
         assert_eq!(score, 2);
     }
     #[test]
@@ -56,7 +226,68 @@ mod tests {
         2-8,3-7
         6-6,4-6
         2-6,4-8"#;
-        let score = part2(&input);
+        let score = part2(input);
         assert_eq!(score, 4);
     }
+    #[test]
+    fn test_relations() {
+        let cases = [
+            ("1-2,4-5", Relation::Before),
+            ("1-3,4-5", Relation::Meets),
+            ("1-4,3-5", Relation::Overlaps),
+            ("1-4,4-5", Relation::Overlaps),
+            ("1-2,1-5", Relation::Starts),
+            ("2-3,1-5", Relation::During),
+            ("3-5,1-5", Relation::Finishes),
+            ("1-5,1-5", Relation::Equals),
+        ];
+        for (line, relation) in cases {
+            let (a, b) = parse_line(line).unwrap();
+            assert_eq!(Relation::between(&a, &b), relation, "{line}");
+            assert_eq!(Relation::between(&b, &a), relation.inverse(), "{line}");
+        }
+        assert_eq!(Relation::Equals.inverse(), Relation::Equals);
+        let max = u32::MAX;
+        let edges = [
+            (format!("0-{max},0-{max}"), Relation::Equals),
+            (format!("5-{max},{max}-{max}"), Relation::FinishedBy),
+            (format!("0-{},{max}-{max}", max - 1), Relation::Meets),
+        ];
+        for (line, relation) in edges {
+            let (a, b) = parse_line(&line).unwrap();
+            assert_eq!(Relation::between(&a, &b), relation, "{line}");
+            assert_eq!(Relation::between(&b, &a), relation.inverse(), "{line}");
+        }
+    }
+    #[test]
+    fn test_histogram() {
+        let counts = histogram("2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8");
+        assert_eq!(counts[Relation::Before as usize], 1);
+        assert_eq!(counts[Relation::Meets as usize], 1);
+        assert_eq!(counts[Relation::Overlaps as usize], 2);
+        assert_eq!(counts[Relation::Contains as usize], 1);
+        assert_eq!(counts[Relation::Finishes as usize], 1);
+        assert_eq!(counts.iter().sum::<usize>(), 6);
+        assert!(report("1-2,3-4").contains("meets     1 #"));
+    }
+    #[test]
+    fn test_double_covered() {
+        // 7, 3-7 (5 sections), 6, 4-6 (3 sections)
+        let input = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8";
+        assert_eq!(double_covered(input), 1 + 5 + 1 + 3);
+        assert_eq!(double_covered(&format!("0-{0},0-{0}", u32::MAX)), 1 << 32);
+    }
+    #[test]
+    fn test_invalid_lines() {
+        assert_eq!(parse_line("2-4"), Err("Missing ',' between ranges"));
+        assert_eq!(parse_line("2-4,6"), Err("Missing '-' in section range"));
+        assert_eq!(
+            parse_line("4-2,6-8"),
+            Err("Section range ends before it starts")
+        );
+        assert_eq!(
+            parse_input("1-2,3-4\n1-x,3-4"),
+            Err("Line 2: Invalid section end".to_string())
+        );
+    }
 }
@@ -1,5 +1,6 @@
-use std::{convert::TryInto, fs, str::FromStr};
+use std::{convert::TryInto, env, fmt::Display, fs, process, str::FromStr};
 
+#[derive(Clone, Debug, PartialEq)]
 struct Instr {
     amount: usize,
     from: usize,
@@ -19,57 +20,326 @@ impl FromStr for Instr {
             nums.try_into().map_err(|_| "Missing numbers in input")?;
         Ok(Instr {
             amount,
-            from: from - 1,
-            to: to - 1,
+            from: from.checked_sub(1).ok_or("Stacks are numbered from 1")?,
+            to: to.checked_sub(1).ok_or("Stacks are numbered from 1")?,
         })
     }
 }
-fn parse_input(input: &str) -> (Vec<Vec<char>>, Vec<Instr>) {
-    let (initial_state, instructions) = input.split_once("\n\n").expect("Invalid input");
-    let n_lines = (initial_state.lines().next().expect("Invalid input").len() + 1) / 4;
 
-    let mut state: Vec<Vec<char>> = vec![vec![]; n_lines];
-    initial_state.lines().rev().skip(1).for_each(|l| {
-        l.chars().skip(1).step_by(4).enumerate().for_each(|(i, e)| {
-            if e.is_alphabetic() {
-                state[i].push(e);
+impl Display for Instr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.amount,
+            self.from + 1,
+            self.to + 1
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum CraneError {
+    NoSuchStack {
+        step: usize,
+        stack: usize,
+    },
+    Underflow {
+        step: usize,
+        stack: usize,
+        requested: usize,
+        available: usize,
+    },
+}
+
+impl Display for CraneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoSuchStack { step, stack } => {
+                write!(f, "Step {}: there is no stack {}", step, stack + 1)
             }
+            Self::Underflow {
+                step,
+                stack,
+                requested,
+                available,
+            } => write!(
+                f,
+                "Step {}: cannot move {} crates from stack {} holding {}",
+                step,
+                requested,
+                stack + 1,
+                available
+            ),
+        }
+    }
+}
+
+type Stacks = Vec<Vec<String>>;
+
+trait Crane {
+    // Number of crates picked up at once, the order within a lift is kept
+    fn capacity(&self) -> usize;
+
+    fn move_crates(&self, from: &mut Vec<String>, to: &mut Vec<String>, amount: usize) {
+        let mut remaining = amount;
+        while remaining > 0 {
+            let lift = remaining.min(self.capacity());
+            let mut crates = from.split_off(from.len() - lift);
+            to.append(&mut crates);
+            remaining -= lift;
+        }
+    }
+}
+
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn capacity(&self) -> usize {
+        1
+    }
+}
+
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+}
+
+// Moves up to `k` crates at a time
+struct BatchCrane(usize);
+
+impl BatchCrane {
+    fn new(k: usize) -> Result<Self, &'static str> {
+        match k {
+            0 => Err("A crane must lift at least one crate"),
+            k => Ok(Self(k)),
+        }
+    }
+}
+
+impl Crane for BatchCrane {
+    fn capacity(&self) -> usize {
+        self.0
+    }
+}
+
+fn apply(
+    crane: &impl Crane,
+    state: &mut [Vec<String>],
+    instr: &Instr,
+    step: usize,
+) -> Result<(), CraneError> {
+    for stack in [instr.from, instr.to] {
+        if stack >= state.len() {
+            return Err(CraneError::NoSuchStack { step, stack });
+        }
+    }
+    let available = state[instr.from].len();
+    if instr.amount > available {
+        return Err(CraneError::Underflow {
+            step,
+            stack: instr.from,
+            requested: instr.amount,
+            available,
         });
-    });
+    }
+    if instr.from == instr.to {
+        return Ok(());
+    }
+    let mut from = std::mem::take(&mut state[instr.from]);
+    crane.move_crates(&mut from, &mut state[instr.to], instr.amount);
+    state[instr.from] = from;
+    Ok(())
+}
 
-    let instructions: Vec<Instr> = instructions.lines().flat_map(Instr::from_str).collect();
+// Runs every instruction, calling `on_step` with the state after each one
+fn run(
+    crane: &impl Crane,
+    mut state: Stacks,
+    instructions: &[Instr],
+    mut on_step: impl FnMut(usize, &Instr, &[Vec<String>]),
+) -> Result<Stacks, CraneError> {
+    for (i, instr) in instructions.iter().enumerate() {
+        apply(crane, &mut state, instr, i + 1)?;
+        on_step(i + 1, instr, &state);
+    }
+    Ok(state)
+}
 
-    (state, instructions)
+// Stack states after every step, starting with the initial one
+fn trace(crane: &impl Crane, input: &str) -> Result<String, CraneError> {
+    let (state, instructions) = parse_input(input);
+    let mut steps = vec![format!("initial\n{}", render_diagram(&state))];
+    run(crane, state, &instructions, |step, instr, state| {
+        steps.push(format!("{step}: {instr}\n{}", render_diagram(state)));
+    })?;
+    Ok(steps.join("\n\n"))
 }
 
-fn part1(input: &str) -> String {
-    let (mut state, instructions) = parse_input(input);
-    for Instr { amount, from, to } in instructions {
-        for _ in 0..amount {
-            if let Some(popped) = state[from].pop() {
-                state[to].push(popped);
+// Spans of the `[name]` crates on a line as (start, end, name), by char index
+fn tokens(line: &str) -> Vec<(usize, usize, &str)> {
+    let mut tokens = vec![];
+    let mut start = None;
+    for (i, (byte_idx, c)) in line.char_indices().enumerate() {
+        match (c, start) {
+            ('[', None) => start = Some((i, byte_idx)),
+            (']', Some((first, first_byte))) => {
+                tokens.push((first, i + 1, line[first_byte + 1..byte_idx].trim()));
+                start = None;
             }
+            _ => {}
         }
     }
+    tokens
+}
 
-    let result = state.iter().flat_map(|v| v.last()).collect::<String>();
-    result
+// Spans of the whitespace separated labels on the footer line
+fn labels(line: &str) -> Vec<(usize, usize, &str)> {
+    let mut labels = vec![];
+    let mut start = None;
+    let chars = line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')));
+    for (i, (byte_idx, c)) in chars.enumerate() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some((i, byte_idx)),
+            (true, Some((first, first_byte))) => {
+                labels.push((first, i, &line[first_byte..byte_idx]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    labels
 }
 
-fn part2(input: &str) -> String {
-    let (mut state, instructions) = parse_input(input);
-    for Instr { amount, from, to } in instructions {
-        let from_idx = state[from].len() - amount;
-        let mut elements = state[from].split_off(from_idx);
-        state[to].append(&mut elements);
+// Reads a stack drawing whose last line numbers the stacks from 1. Crates belong to
+// the stack whose label they are drawn above, so any widths and ragged lines work.
+fn parse_diagram(diagram: &str) -> Result<Stacks, &'static str> {
+    let mut lines = diagram.lines().rev().skip_while(|l| l.trim().is_empty());
+    let footer = labels(lines.next().ok_or("Empty stack diagram")?);
+    for (i, &(_, _, label)) in footer.iter().enumerate() {
+        if label.parse::<usize>() != Ok(i + 1) {
+            return Err("Stacks must be labelled 1, 2, 3, ... from left to right");
+        }
     }
 
-    let result = state.iter().flat_map(|v| v.last()).collect::<String>();
-    result
+    let mut state: Stacks = vec![vec![]; footer.len()];
+    for line in lines {
+        for (start, end, name) in tokens(line) {
+            let stack = footer
+                .iter()
+                .position(|&(l_start, l_end, _)| start < l_end && l_start < end)
+                .ok_or("Crate is not above a stack label")?;
+            if name.is_empty() {
+                return Err("Crate without a name");
+            }
+            state[stack].push(name.to_string());
+        }
+    }
+    Ok(state)
+}
+
+fn centered(text: &str, width: usize) -> String {
+    let left = (width - text.chars().count()) / 2;
+    format!("{:left$}{:<rest$}", "", text, rest = width - left)
+}
+
+// Draws the stacks the way the puzzle input does, including the label row
+fn render_diagram(state: &[Vec<String>]) -> String {
+    let widths = state
+        .iter()
+        .enumerate()
+        .map(|(i, stack)| {
+            let widest = stack
+                .iter()
+                .map(|c| c.chars().count() + 2)
+                .max()
+                .unwrap_or(3);
+            widest.max((i + 1).to_string().len())
+        })
+        .collect::<Vec<usize>>();
+    let height = state.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut lines = (0..height)
+        .rev()
+        .map(|level| {
+            state
+                .iter()
+                .zip(&widths)
+                .map(|(stack, &w)| match stack.get(level) {
+                    Some(name) => centered(&format!("[{name}]"), w),
+                    None => " ".repeat(w),
+                })
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect::<Vec<String>>();
+    lines.push(
+        widths
+            .iter()
+            .enumerate()
+            .map(|(i, &w)| centered(&(i + 1).to_string(), w))
+            .collect::<Vec<String>>()
+            .join(" "),
+    );
+    lines.join("\n")
+}
+
+fn parse_input(input: &str) -> (Stacks, Vec<Instr>) {
+    let (initial_state, instructions) = input.split_once("\n\n").expect("Invalid input");
+    let state = parse_diagram(initial_state).expect("Invalid stack diagram");
+
+    let instructions: Vec<Instr> = instructions
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(Instr::from_str)
+        .collect::<Result<_, _>>()
+        .expect("Invalid instruction");
+
+    (state, instructions)
+}
+
+fn top_crates(crane: &impl Crane, input: &str) -> Result<String, CraneError> {
+    let (state, instructions) = parse_input(input);
+    let state = run(crane, state, &instructions, |_, _, _| {})?;
+    Ok(state
+        .iter()
+        .flat_map(|v| v.last())
+        .map(String::as_str)
+        .collect::<String>())
+}
+
+fn part1(input: &str) -> String {
+    top_crates(&CrateMover9000, input).unwrap_or_else(|e| panic!("{e}"))
+}
+
+fn part2(input: &str) -> String {
+    top_crates(&CrateMover9001, input).unwrap_or_else(|e| panic!("{e}"))
 }
 
 fn main() {
     let input = fs::read_to_string("inputs/day05.txt").expect("Could not read input file");
+    let mut args = env::args().skip(1);
+    if let Some("--trace") = args.next().as_deref() {
+        let fail = |e: &dyn Display| -> ! {
+            eprintln!("{e}");
+            process::exit(1)
+        };
+        let capacity = match args.next() {
+            None => 1,
+            Some(k) => k
+                .parse()
+                .unwrap_or_else(|_| fail(&"Invalid crate count after --trace")),
+        };
+        let crane = BatchCrane::new(capacity).unwrap_or_else(|e| fail(&e));
+        let trace = trace(&crane, &input).unwrap_or_else(|e| fail(&e));
+        println!("{trace}");
+        return;
+    }
     let part1 = part1(&input);
     let part2 = part2(&input);
     println!("Part 1: {part1}");
@@ -91,6 +361,10 @@ move 3 from 1 to 3
 move 2 from 2 to 1
 move 1 from 1 to 2";
 
+    fn crates(names: &str) -> Vec<String> {
+        names.split_whitespace().map(str::to_string).collect()
+    }
+
     #[test]
     fn test_part1() {
         let msg = part1(TEST_INPUT.trim_start_matches('\n'));
@@ -101,4 +375,118 @@ move 1 from 1 to 2";
         let msg = part2(TEST_INPUT.trim_start_matches('\n'));
         assert_eq!(msg, "MCD");
     }
+    #[test]
+    fn test_batch_crane() {
+        let input = TEST_INPUT.trim_start_matches('\n');
+        assert_eq!(
+            top_crates(&BatchCrane::new(1).unwrap(), input),
+            Ok("CMZ".to_string())
+        );
+        assert_eq!(
+            top_crates(&BatchCrane::new(3).unwrap(), input),
+            Ok("MCD".to_string())
+        );
+        // Moving [Z N D] two at a time puts [N D] down first, then [Z]
+        let mut stack = vec![];
+        assert!(BatchCrane::new(0).is_err());
+        BatchCrane::new(2)
+            .unwrap()
+            .move_crates(&mut crates("Z N D"), &mut stack, 3);
+        assert_eq!(stack, crates("N D Z"));
+    }
+    #[test]
+    fn test_trace() {
+        let trace = trace(&CrateMover9000, TEST_INPUT.trim_start_matches('\n')).unwrap();
+        let steps = trace.split("\n\n").collect::<Vec<&str>>();
+        assert_eq!(steps.len(), 5);
+        assert_eq!(
+            steps[0],
+            "initial\n    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 "
+        );
+        assert_eq!(
+            steps[1],
+            "1: move 1 from 2 to 1\n[D]        \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 "
+        );
+        assert_eq!(
+            steps[4],
+            "4: move 1 from 1 to 2\n        [Z]\n        [N]\n        [D]\n[C] [M] [P]\n 1   2   3 "
+        );
+    }
+    #[test]
+    fn test_errors() {
+        let state = vec![crates("A"), vec![]];
+        let instr = |s: &str| vec![s.parse::<Instr>().unwrap()];
+        assert_eq!(
+            run(
+                &CrateMover9001,
+                state.clone(),
+                &instr("move 2 from 1 to 2"),
+                |_, _, _| {}
+            ),
+            Err(CraneError::Underflow {
+                step: 1,
+                stack: 0,
+                requested: 2,
+                available: 1
+            })
+        );
+        assert_eq!(
+            run(
+                &CrateMover9000,
+                state,
+                &instr("move 1 from 1 to 3"),
+                |_, _, _| {}
+            ),
+            Err(CraneError::NoSuchStack { step: 1, stack: 2 })
+        );
+        assert!("move 1 from 0 to 1".parse::<Instr>().is_err());
+    }
+    #[test]
+    fn test_diagram_round_trip() {
+        let diagram = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 ";
+        let state = parse_diagram(diagram).unwrap();
+        assert_eq!(state, vec![crates("Z N"), crates("M C D"), crates("P")]);
+        assert_eq!(render_diagram(&state), diagram);
+
+        let state = vec![
+            crates("A"),
+            vec![],
+            crates("B C"),
+            crates("D"),
+            crates("E"),
+            crates("F"),
+            crates("G"),
+            crates("H"),
+            crates("I"),
+            crates("J K"),
+            crates("L"),
+        ];
+        let diagram = render_diagram(&state);
+        assert_eq!(
+            diagram.lines().last(),
+            Some(" 1   2   3   4   5   6   7   8   9  10  11 ")
+        );
+        assert_eq!(parse_diagram(&diagram), Ok(state));
+    }
+    #[test]
+    fn test_multi_character_crates() {
+        let state = vec![crates("AB XYZ"), crates("Q"), crates("LONG")];
+        let diagram = render_diagram(&state);
+        assert_eq!(
+            diagram,
+            "[XYZ]           \n[AB]  [Q] [LONG]\n  1    2    3   "
+        );
+        assert_eq!(parse_diagram(&diagram), Ok(state));
+    }
+    #[test]
+    fn test_ragged_diagram() {
+        // Trailing spaces trimmed and labels not centred
+        let diagram = "    [D]\n[N] [C]\n[Z] [M] [P]\n1   2   3";
+        assert_eq!(
+            parse_diagram(diagram),
+            Ok(vec![crates("Z N"), crates("M C D"), crates("P")])
+        );
+        assert!(parse_diagram("[A]\n 2").is_err());
+        assert!(parse_diagram("        [A]\n 1   2").is_err());
+    }
 }
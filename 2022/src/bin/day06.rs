@@ -1,46 +1,320 @@
-use std::fs;
+use std::{
+    env,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    ops::RangeInclusive,
+};
 
-fn start_marker(input: &str, size: usize) -> usize {
-    let characters: Vec<char> = input.chars().collect();
-    characters
-        .windows(size)
-        .position(|chars| !(1..chars.len()).any(|i| chars[i..].contains(&chars[i - 1])))
-        .expect("Start marker not found")
-        + size
+// No window longer than the number of byte values can hold only distinct bytes
+const MAX_MARKER: usize = 256;
+
+// Sliding window over the last `size` bytes, tracking how many of them are distinct
+struct MarkerDetector {
+    size: usize,
+    counts: [usize; 256],
+    window: Vec<u8>, // ring buffer, byte i of the stream at index i % size
+    distinct: usize,
+    position: usize, // number of bytes pushed so far
+}
+
+impl MarkerDetector {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            counts: [0; 256],
+            window: vec![0; size.min(MAX_MARKER)],
+            distinct: 0,
+            position: 0,
+        }
+    }
+
+    // Adds the next byte of the stream, returning true if it completes a marker
+    fn push(&mut self, byte: u8) -> bool {
+        if self.size == 0 || self.size > MAX_MARKER {
+            return false;
+        }
+        let slot = self.position % self.size;
+        if self.position >= self.size {
+            let old = self.window[slot] as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 0 {
+                self.distinct -= 1;
+            }
+        }
+        self.window[slot] = byte;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 1 {
+            self.distinct += 1;
+        }
+        self.position += 1;
+        self.distinct == self.size
+    }
+}
+
+// Positions after every marker in a stream, i.e. the number of bytes read
+// when the last `size` of them were all different
+struct Markers<R> {
+    bytes: io::Bytes<R>,
+    detector: MarkerDetector,
+}
+
+impl<R: BufRead> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
+    fn next(&mut self) -> Option<Self::Item> {
+        for byte in self.bytes.by_ref() {
+            match byte {
+                Ok(byte) if self.detector.push(byte) => return Some(Ok(self.detector.position)),
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        None
+    }
+}
+
+fn markers<R: BufRead>(reader: R, size: usize) -> Markers<R> {
+    Markers {
+        bytes: reader.bytes(),
+        detector: MarkerDetector::new(size),
+    }
+}
+
+fn start_marker(reader: impl BufRead, size: usize) -> io::Result<Option<usize>> {
+    markers(reader, size).next().transpose()
+}
+
+// First marker of every window size and the longest run of distinct bytes,
+// gathered in one pass. A marker of size k first ends where the longest
+// distinct run seen so far first reaches length k.
+#[derive(Debug, PartialEq)]
+struct Signal {
+    first_markers: Vec<usize>, // index k - 1 holds the first marker of size k
+    length: usize,
+}
+
+impl Signal {
+    fn analyse(reader: impl BufRead) -> io::Result<Self> {
+        let mut last_seen: [Option<usize>; 256] = [None; 256];
+        let mut first_markers = vec![];
+        let mut run_start = 0;
+        let mut length = 0;
+        for byte in reader.bytes() {
+            let byte = byte? as usize;
+            if let Some(i) = last_seen[byte] {
+                run_start = run_start.max(i + 1);
+            }
+            last_seen[byte] = Some(length);
+            length += 1;
+            while first_markers.len() < length - run_start {
+                first_markers.push(length);
+            }
+        }
+        Ok(Self {
+            first_markers,
+            length,
+        })
+    }
+
+    fn first_marker(&self, size: usize) -> Option<usize> {
+        size.checked_sub(1)
+            .and_then(|i| self.first_markers.get(i).copied())
+    }
+
+    // Earliest longest run of distinct bytes as (start, length)
+    fn longest_run(&self) -> (usize, usize) {
+        let len = self.first_markers.len();
+        (self.first_markers.last().map_or(0, |end| end - len), len)
+    }
+
+    fn report(&self, sizes: RangeInclusive<usize>) -> String {
+        let mut lines = sizes
+            .map(|size| match self.first_marker(size) {
+                Some(position) => format!("Size {:>3}: first marker at {}", size, position),
+                None => format!("Size {:>3}: no marker", size),
+            })
+            .collect::<Vec<String>>();
+        let (start, len) = self.longest_run();
+        lines.push(format!(
+            "Longest distinct run: {} bytes starting at {} of {}",
+            len, start, self.length
+        ));
+        lines.join("\n")
+    }
 }
-fn part1(input: &str) -> usize {
-    start_marker(input, 4)
+
+fn part1(reader: impl BufRead) -> Option<usize> {
+    start_marker(reader, 4).expect("Could not read input")
 }
 
-fn part2(input: &str) -> usize {
-    start_marker(input, 14)
+fn part2(reader: impl BufRead) -> Option<usize> {
+    start_marker(reader, 14).expect("Could not read input")
+}
+
+fn open_input() -> BufReader<File> {
+    BufReader::new(File::open("inputs/day06.txt").expect("Could not read input file"))
+}
+
+fn print_marker(name: &str, marker: Option<usize>) {
+    match marker {
+        Some(position) => println!("{name}: {position}"),
+        None => println!("{name}: no marker found"),
+    }
 }
 
 fn main() {
-    let input = fs::read_to_string("inputs/day06.txt").expect("Could not read input file");
-    let part1 = part1(&input);
-    let part2 = part2(&input);
-    println!("Part 1: {part1}");
-    println!("Part 2: {part2}");
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--report" {
+            let mut size = || args.next().and_then(|n| n.parse().ok());
+            let (min, max) = (size().unwrap_or(1), size().unwrap_or(26));
+            let signal = Signal::analyse(open_input()).expect("Could not read input");
+            println!("{}", signal.report(min..=max));
+            return;
+        }
+        if arg == "--markers" {
+            let size = args
+                .next()
+                .and_then(|n| n.parse().ok())
+                .expect("Expected a window size after --markers");
+            let positions = markers(open_input(), size)
+                .collect::<io::Result<Vec<usize>>>()
+                .expect("Could not read input");
+            println!("Markers of size {size}: {positions:?}");
+            return;
+        }
+    }
+    print_marker("Part 1", part1(open_input()));
+    print_marker("Part 2", part2(open_input()));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // Reference implementation comparing every pair in each window
+    fn naive_markers(input: &[u8], size: usize) -> Vec<usize> {
+        if size == 0 {
+            return vec![];
+        }
+        input
+            .windows(size)
+            .enumerate()
+            .filter(|(_, w)| (1..w.len()).all(|i| !w[i..].contains(&w[i - 1])))
+            .map(|(i, _)| i + size)
+            .collect()
+    }
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1("bvwbjplbgvbhsrlpgdmjqwftvncz"), 5);
-        assert_eq!(part1("nppdvjthqldpwncqszvftbrmjlhg"), 6);
-        assert_eq!(part1("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 10);
-        assert_eq!(part1("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 11);
+        assert_eq!(part1("bvwbjplbgvbhsrlpgdmjqwftvncz".as_bytes()), Some(5));
+        assert_eq!(part1("nppdvjthqldpwncqszvftbrmjlhg".as_bytes()), Some(6));
+        assert_eq!(
+            part1("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".as_bytes()),
+            Some(10)
+        );
+        assert_eq!(
+            part1("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".as_bytes()),
+            Some(11)
+        );
     }
     #[test]
     fn test_part2() {
-        assert_eq!(part2("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), 19);
-        assert_eq!(part2("bvwbjplbgvbhsrlpgdmjqwftvncz"), 23);
-        assert_eq!(part2("nppdvjthqldpwncqszvftbrmjlhg"), 23);
-        assert_eq!(part2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 29);
-        assert_eq!(part2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 26);
+        assert_eq!(part2("mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes()), Some(19));
+        assert_eq!(part2("bvwbjplbgvbhsrlpgdmjqwftvncz".as_bytes()), Some(23));
+        assert_eq!(part2("nppdvjthqldpwncqszvftbrmjlhg".as_bytes()), Some(23));
+        assert_eq!(
+            part2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".as_bytes()),
+            Some(29)
+        );
+        assert_eq!(
+            part2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".as_bytes()),
+            Some(26)
+        );
+    }
+    #[test]
+    fn test_no_marker() {
+        assert_eq!(part1("abcabcabc".as_bytes()), None);
+        assert_eq!(part1("abc".as_bytes()), None);
+        assert_eq!(part2("".as_bytes()), None);
+        assert_eq!(start_marker("abc".as_bytes(), 0).unwrap(), None);
+    }
+    #[test]
+    fn test_all_markers() {
+        let all = markers("abcabcd".as_bytes(), 3)
+            .collect::<io::Result<Vec<usize>>>()
+            .unwrap();
+        assert_eq!(all, vec![3, 4, 5, 6, 7]);
+        let all = markers("aabbcc".as_bytes(), 2)
+            .collect::<io::Result<Vec<usize>>>()
+            .unwrap();
+        assert_eq!(all, vec![3, 5]);
+    }
+    #[test]
+    fn test_byte_alphabet() {
+        // Every byte value once, then the same bytes again in reverse
+        let input = (0..=255u8).chain((0..=255u8).rev()).collect::<Vec<u8>>();
+        assert_eq!(start_marker(&input[..], 256).unwrap(), Some(256));
+        let all = markers(&input[..], 200)
+            .collect::<io::Result<Vec<usize>>>()
+            .unwrap();
+        assert_eq!(all, naive_markers(&input, 200));
+    }
+    #[test]
+    fn test_window_slides() {
+        // Duplicates leaving the window make the next window distinct again
+        for (input, size, expected) in [
+            ("abcab", 3, vec![3, 4, 5]),
+            ("abba", 2, vec![2, 4]),
+            ("aaaa", 1, vec![1, 2, 3, 4]),
+            ("abcdbcdea", 4, vec![4, 8, 9]),
+        ] {
+            let all = markers(input.as_bytes(), size)
+                .collect::<io::Result<Vec<usize>>>()
+                .unwrap();
+            assert_eq!(all, expected, "{input} with size {size}");
+            assert_eq!(all, naive_markers(input.as_bytes(), size));
+        }
+    }
+    #[test]
+    fn test_oversized_window() {
+        assert_eq!(MarkerDetector::new(usize::MAX).window.len(), MAX_MARKER);
+        let input = (0..=255u8).chain([0]).collect::<Vec<u8>>();
+        assert_eq!(start_marker(&input[..], 257).unwrap(), None);
+        assert_eq!(start_marker(&input[..], usize::MAX).unwrap(), None);
+    }
+    #[test]
+    fn test_signal_report() {
+        let signal = Signal::analyse("mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes()).unwrap();
+        assert_eq!(signal.first_marker(4), Some(7));
+        assert_eq!(signal.first_marker(14), Some(19));
+        assert_eq!(signal.first_marker(0), None);
+        // "phdztnvjfqwrcgsmlb" runs to the end of the stream
+        assert_eq!(signal.longest_run(), (12, 18));
+        assert_eq!(signal.first_marker(18), Some(30));
+        assert_eq!(signal.first_marker(19), None);
+        assert_eq!(
+            signal.report(17..=19),
+            "Size  17: first marker at 29\n\
+             Size  18: first marker at 30\n\
+             Size  19: no marker\n\
+             Longest distinct run: 18 bytes starting at 12 of 30"
+        );
+        let empty = Signal::analyse("".as_bytes()).unwrap();
+        assert_eq!(empty.longest_run(), (0, 0));
+        assert_eq!(empty.first_marker(1), None);
+    }
+    #[test]
+    fn test_signal_matches_detector() {
+        // Runs of growing length, the longest one followed by shorter repeats
+        let input = "aababcabcdabcdeaab";
+        let signal = Signal::analyse(input.as_bytes()).unwrap();
+        let expected = [Some(1), Some(3), Some(6), Some(10), Some(15), None];
+        for (size, expected) in (1..).zip(expected) {
+            assert_eq!(signal.first_marker(size), expected, "size {size}");
+            assert_eq!(start_marker(input.as_bytes(), size).unwrap(), expected);
+        }
+        // "abcde" and "bcdea" are both five bytes long, the earlier one wins
+        assert_eq!(signal.longest_run(), (10, 5));
+        assert_eq!(signal.length, 18);
     }
 }
@@ -1,148 +1,695 @@
-use std::fs;
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    env,
+    fmt::Display,
+    fs,
+    io::{self, BufRead, Write},
+};
 
-enum Command<'a> {
-    Cd(&'a str),
-    Ls(Vec<Node<'a>>),
+#[derive(Debug, PartialEq)]
+enum FsError {
+    UnknownCommand { line: usize },
+    InvalidEntry { line: usize },
+    UnexpectedOutput { line: usize },
+    NoSuchDirectory { line: usize, path: String },
+    Conflict { line: usize, path: String },
 }
 
-impl<'a> TryFrom<&'a str> for Command<'a> {
-    type Error = &'static str;
-    fn try_from(s: &'a str) -> Result<Command<'a>, Self::Error> {
-        let mut lines = s.lines();
-        let cmd = lines.next().ok_or("Missing command")?.trim();
-        if cmd == "ls" {
-            let output = lines.flat_map(Node::try_from).collect::<Vec<Node>>();
-            Ok(Self::Ls(output))
-        } else {
-            let (_, dir) = cmd.split_once(' ').ok_or("Missing argument for cd")?;
-            Ok(Self::Cd(dir))
+impl Display for FsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownCommand { line } => write!(f, "Unknown command on line {}", line),
+            Self::InvalidEntry { line } => write!(f, "Invalid ls output on line {}", line),
+            Self::UnexpectedOutput { line } => {
+                write!(f, "Output without a preceding ls on line {}", line)
+            }
+            Self::NoSuchDirectory { line, path } => {
+                write!(f, "No such directory {} on line {}", path, line)
+            }
+            Self::Conflict { line, path } => {
+                write!(
+                    f,
+                    "Listing on line {} conflicts with existing {}",
+                    line, path
+                )
+            }
         }
     }
 }
 
-struct Node<'a> {
-    name: &'a str,
-    size: Option<u32>,
-    children: Vec<Node<'a>>,
+// An entry of `ls` output
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Entry {
+    File(u64),
+    Dir,
 }
 
-impl<'a> Node<'a> {
-    fn size(&self) -> u32 {
-        self.size
-            .unwrap_or_else(|| self.children.iter().map(Self::size).sum())
+impl Entry {
+    // An `ls` line such as "dir a" or "1234 b.txt", with the entry name
+    fn parse(line: &str) -> Option<(Self, &str)> {
+        let (entry, name) = line.split_once(' ')?;
+        if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+            return None;
+        }
+        match entry {
+            "dir" => Some((Self::Dir, name)),
+            size => Some((Self::File(size.parse().ok()?), name)),
+        }
     }
-    fn get_child(&mut self, name: &str) -> Option<&mut Self> {
-        self.children.iter_mut().find(|c| c.name == name)
+}
+
+#[derive(Debug)]
+enum Kind {
+    File,
+    Dir(BTreeMap<String, usize>), // children by name
+}
+
+#[derive(Debug)]
+struct Node {
+    name: String,
+    parent: usize, // the root is its own parent
+    size: u64,     // for directories the total of everything below, kept up to date
+    kind: Kind,
+}
+
+// Tree of files and directories stored in an arena, nodes referring to each other by index
+struct FileSystem {
+    nodes: Vec<Node>,
+}
+
+impl FileSystem {
+    const ROOT: usize = 0;
+
+    fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                name: String::new(),
+                parent: Self::ROOT,
+                size: 0,
+                kind: Kind::Dir(BTreeMap::new()),
+            }],
+        }
     }
-    fn directory_sizes(&self) -> Vec<u32> {
-        // This is pretty inefficient, but good enough for now
-        let self_size: u32 = self.children.iter().map(Node::size).sum();
-        let mut dir_sizes = self
-            .children
-            .iter()
-            .filter(|c| !c.children.is_empty())
-            .flat_map(Node::directory_sizes)
-            .collect::<Vec<u32>>();
-        if !self.children.is_empty() {
-            dir_sizes.push(self_size);
+
+    // Replays a terminal session of `cd` and `ls` commands
+    fn from_transcript(input: &str) -> Result<Self, FsError> {
+        let mut fs = Self::new();
+        let mut cwd = Self::ROOT;
+        let mut listing = false;
+        for (i, line) in input.lines().enumerate() {
+            let (line_no, line) = (i + 1, line.trim());
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(cmd) = line.strip_prefix('$') {
+                let mut args = cmd.split_whitespace();
+                listing = false;
+                match (args.next(), args.next(), args.next()) {
+                    (Some("ls"), None, _) => listing = true,
+                    (Some("cd"), Some(path), None) => {
+                        cwd = fs
+                            .resolve(cwd, path)
+                            .filter(|&id| fs.is_dir(id))
+                            .ok_or_else(|| FsError::NoSuchDirectory {
+                                line: line_no,
+                                path: path.to_string(),
+                            })?;
+                    }
+                    _ => return Err(FsError::UnknownCommand { line: line_no }),
+                }
+            } else if listing {
+                let (entry, name) =
+                    Entry::parse(line).ok_or(FsError::InvalidEntry { line: line_no })?;
+                fs.add(cwd, name, entry).map_err(|path| FsError::Conflict {
+                    line: line_no,
+                    path,
+                })?;
+            } else {
+                return Err(FsError::UnexpectedOutput { line: line_no });
+            }
         }
-        dir_sizes
+        Ok(fs)
+    }
+
+    fn is_dir(&self, id: usize) -> bool {
+        matches!(self.nodes[id].kind, Kind::Dir(_))
+    }
+
+    fn size(&self, id: usize) -> u64 {
+        self.nodes[id].size
+    }
+
+    fn child(&self, id: usize, name: &str) -> Option<usize> {
+        match &self.nodes[id].kind {
+            Kind::Dir(children) => children.get(name).copied(),
+            Kind::File => None,
+        }
+    }
+
+    fn children(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        match &self.nodes[id].kind {
+            Kind::Dir(children) => Some(children.values().copied()),
+            Kind::File => None,
+        }
+        .into_iter()
+        .flatten()
+    }
+
+    // Every node below and including `id` in pre-order, with its depth below `id`
+    fn walk(&self, id: usize) -> Vec<(usize, usize)> {
+        let mut order = vec![];
+        let mut stack = vec![(id, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            order.push((id, depth));
+            let children = self.children(id).collect::<Vec<usize>>();
+            stack.extend(children.into_iter().rev().map(|c| (c, depth + 1)));
+        }
+        order
+    }
+
+    fn directories(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.nodes.len()).filter(|&id| self.is_dir(id))
+    }
+
+    fn path(&self, mut id: usize) -> String {
+        let mut names = vec![];
+        while id != Self::ROOT {
+            names.push(self.nodes[id].name.as_str());
+            id = self.nodes[id].parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    // Follows a path relative to `from`, or from the root if it starts with '/'
+    fn resolve(&self, from: usize, path: &str) -> Option<usize> {
+        let start = if path.starts_with('/') {
+            Self::ROOT
+        } else {
+            from
+        };
+        path.split('/').try_fold(start, |id, name| match name {
+            "" | "." => Some(id),
+            ".." => Some(self.nodes[id].parent),
+            name => self.child(id, name),
+        })
     }
-    fn get_node(&mut self, path: &Vec<&str>) -> Option<&mut Self> {
-        let mut node: Option<&mut Self> = Some(self);
-        for p in path {
-            node = node.and_then(|e| e.get_child(p));
+
+    // Adds an entry to a directory. Listing an existing entry again is a no-op,
+    // unless it disagrees with what is known, in which case its path is returned.
+    fn add(&mut self, dir: usize, name: &str, entry: Entry) -> Result<usize, String> {
+        if let Some(id) = self.child(dir, name) {
+            return match (entry, &self.nodes[id].kind) {
+                (Entry::Dir, Kind::Dir(_)) => Ok(id),
+                (Entry::File(size), Kind::File) if size == self.nodes[id].size => Ok(id),
+                _ => Err(self.path(id)),
+            };
         }
-        node
+        let id = self.nodes.len();
+        let (size, kind) = match entry {
+            Entry::File(size) => (size, Kind::File),
+            Entry::Dir => (0, Kind::Dir(BTreeMap::new())),
+        };
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: dir,
+            size,
+            kind,
+        });
+        if let Kind::Dir(children) = &mut self.nodes[dir].kind {
+            children.insert(name.to_string(), id);
+        }
+        let mut ancestor = dir;
+        loop {
+            self.nodes[ancestor].size += size;
+            if ancestor == Self::ROOT {
+                break;
+            }
+            ancestor = self.nodes[ancestor].parent;
+        }
+        Ok(id)
     }
-    fn add_children(&mut self, children: &mut Vec<Self>) {
-        self.children.append(children);
+}
+
+// Size with a binary unit suffix, as `du -h` would show it
+fn human_size(size: u64) -> String {
+    let units = ["K", "M", "G", "T"];
+    if size < 1024 {
+        return size.to_string();
     }
+    let mut size = size as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < units.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", size, units[unit])
 }
 
-impl<'a> TryFrom<&'a str> for Node<'a> {
-    type Error = &'static str;
+// Matches `*` (any run of characters) and `?` (any one character)
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = (
+        pattern.chars().collect::<Vec<char>>(),
+        name.chars().collect::<Vec<char>>(),
+    );
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None; // after the last '*', and where it resumed
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, resume)) => {
+                    backtrack = Some((star, resume + 1));
+                    p = star;
+                    n = resume + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
 
-    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
-        let (first, second) = s.split_once(' ').ok_or("Invalid file or dir")?;
-        if first == "dir" {
-            let dir = Self {
-                name: second,
-                size: None,
-                children: vec![],
+#[derive(Debug, PartialEq)]
+enum Predicate {
+    Name(String),
+    Larger(u64),
+    Smaller(u64),
+    Size(u64),
+    Files,
+    Dirs,
+}
+
+impl Predicate {
+    // Options in the style of find(1): -name GLOB, -size [+-]N, -type f|d
+    fn parse_all<'a>(mut args: impl Iterator<Item = &'a str>) -> Result<Vec<Self>, &'static str> {
+        let mut predicates = vec![];
+        while let Some(arg) = args.next() {
+            let value = args.next().ok_or("Missing value for find option")?;
+            let predicate = match (arg, value) {
+                ("-name", glob) => Self::Name(glob.to_string()),
+                ("-type", "f") => Self::Files,
+                ("-type", "d") => Self::Dirs,
+                ("-type", _) => return Err("Expected f or d after -type"),
+                ("-size", size) => {
+                    let parse = |n: &str| n.parse().map_err(|_| "Invalid size for -size");
+                    if let Some(n) = size.strip_prefix('+') {
+                        Self::Larger(parse(n)?)
+                    } else if let Some(n) = size.strip_prefix('-') {
+                        Self::Smaller(parse(n)?)
+                    } else {
+                        Self::Size(parse(size)?)
+                    }
+                }
+                _ => return Err("Unknown find option"),
             };
-            Ok(dir)
-        } else {
-            let size = first.parse::<u32>().map_err(|_| "Invalid size for file")?;
-            let file = Self {
-                name: second,
-                size: Some(size),
-                children: vec![],
+            predicates.push(predicate);
+        }
+        Ok(predicates)
+    }
+
+    fn matches(&self, fs: &FileSystem, id: usize) -> bool {
+        match self {
+            Self::Name(glob) => glob_match(glob, &fs.nodes[id].name),
+            Self::Larger(size) => fs.size(id) > *size,
+            Self::Smaller(size) => fs.size(id) < *size,
+            Self::Size(size) => fs.size(id) == *size,
+            Self::Files => !fs.is_dir(id),
+            Self::Dirs => fs.is_dir(id),
+        }
+    }
+}
+
+// Indented tree of sizes and names below `id`, directories only unless `all` is set
+fn du(fs: &FileSystem, id: usize, all: bool) -> String {
+    fs.walk(id)
+        .into_iter()
+        .filter(|&(id, _)| all || fs.is_dir(id))
+        .map(|(id, depth)| {
+            let name = match (id == FileSystem::ROOT, fs.is_dir(id)) {
+                (true, _) => "/".to_string(),
+                (false, true) => format!("{}/", fs.nodes[id].name),
+                (false, false) => fs.nodes[id].name.clone(),
             };
-            Ok(file)
+            format!(
+                "{:>7}  {}{}",
+                human_size(fs.size(id)),
+                "  ".repeat(depth),
+                name
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Paths below `id` matching every predicate
+fn find(fs: &FileSystem, id: usize, predicates: &[Predicate]) -> Vec<String> {
+    fs.walk(id)
+        .into_iter()
+        .filter(|&(id, _)| predicates.iter().all(|p| p.matches(fs, id)))
+        .map(|(id, _)| fs.path(id))
+        .collect()
+}
+
+// The `n` largest files or directories, largest first
+fn largest(fs: &FileSystem, n: usize, dirs: bool) -> Vec<usize> {
+    let mut ids = (0..fs.nodes.len())
+        .filter(|&id| fs.is_dir(id) == dirs)
+        .collect::<Vec<usize>>();
+    ids.sort_by_key(|&id| Reverse(fs.size(id)));
+    ids.truncate(n);
+    ids
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
         }
     }
+    out.push('"');
+    out
 }
 
-fn build_file_hierarchy(commands: Vec<Command>) -> Node {
-    let mut root = Node {
-        name: "",
-        children: vec![Node {
-            name: "/",
-            children: vec![],
-            size: None,
-        }],
-        size: None,
+// Whole hierarchy below `id`, one node per line
+fn json(fs: &FileSystem, id: usize, depth: usize, out: &mut String) {
+    let node = &fs.nodes[id];
+    let name = if id == FileSystem::ROOT {
+        "/"
+    } else {
+        &node.name
     };
+    let indent = "  ".repeat(depth);
+    let kind = if fs.is_dir(id) { "dir" } else { "file" };
+    out.push_str(&format!(
+        "{}{{\"name\": {}, \"type\": \"{}\", \"size\": {}",
+        indent,
+        json_string(name),
+        kind,
+        node.size
+    ));
+    if fs.is_dir(id) {
+        let children = fs.children(id).collect::<Vec<usize>>();
+        if children.is_empty() {
+            out.push_str(", \"children\": []");
+        } else {
+            out.push_str(", \"children\": [\n");
+            for (i, &child) in children.iter().enumerate() {
+                json(fs, child, depth + 1, out);
+                out.push_str(if i + 1 < children.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&format!("{}]", indent));
+        }
+    }
+    out.push('}');
+}
 
-    let mut current_path: Vec<&str> = vec![];
-    for cmd in commands.into_iter() {
-        match cmd {
-            Command::Cd(path) => {
-                if path == ".." {
-                    current_path.pop();
-                } else {
-                    current_path.push(path);
-                }
+// Plans which directories to delete to make room for an update
+struct Cleanup {
+    disk: u64,
+    required: u64,
+}
+
+impl Cleanup {
+    fn new(disk: u64, required: u64) -> Self {
+        Self { disk, required }
+    }
+
+    fn to_free(&self, fs: &FileSystem) -> Result<u64, &'static str> {
+        let free = self
+            .disk
+            .checked_sub(fs.size(FileSystem::ROOT))
+            .ok_or("Filesystem is larger than the disk")?;
+        Ok(self.required.saturating_sub(free))
+    }
+
+    // The smallest directory freeing enough space, or nothing if there already is enough
+    fn smallest_dir(&self, fs: &FileSystem) -> Result<Option<usize>, &'static str> {
+        let to_free = self.to_free(fs)?;
+        if to_free == 0 {
+            return Ok(None);
+        }
+        fs.directories()
+            .filter(|&id| fs.size(id) >= to_free)
+            .min_by_key(|&id| fs.size(id))
+            .map(Some)
+            .ok_or("No directory frees enough space")
+    }
+
+    // Directories, none inside another, freeing enough space with the smallest total.
+    // This is subset sum over the tree, see `sweep`. The last directory deleted in a
+    // best set is always added to a total below `to_free`, or that total would already
+    // be enough and smaller, so only totals below `to_free` are ever tracked.
+    fn smallest_set(&self, fs: &FileSystem) -> Result<Vec<usize>, &'static str> {
+        let to_free = self.to_free(fs)?;
+        if to_free == 0 {
+            return Ok(vec![]);
+        }
+        // Also keeps the bitsets below no wider than the file system is large
+        if to_free > fs.size(FileSystem::ROOT) {
+            return Err("No directory frees enough space");
+        }
+        // Directories in pre-order, with the position just past each one's subtree,
+        // set once a directory no deeper than it comes up or the walk ends
+        let mut order = vec![];
+        let mut end = vec![];
+        let mut open: Vec<(usize, usize)> = vec![]; // (position, depth)
+        for (id, depth) in fs.walk(FileSystem::ROOT) {
+            if !fs.is_dir(id) {
+                continue;
             }
-            Command::Ls(mut contents) => {
-                if let Some(current_dir) = root.get_node(&current_path) {
-                    current_dir.add_children(&mut contents);
-                };
+            while let Some(&(i, _)) = open.last().filter(|&&(_, d)| d >= depth) {
+                end[i] = order.len();
+                open.pop();
             }
-        };
+            open.push((order.len(), depth));
+            order.push(id);
+            end.push(0);
+        }
+        for (i, _) in open {
+            end[i] = order.len();
+        }
+        let sizes = order.iter().map(|&id| fs.size(id)).collect::<Vec<u64>>();
+
+        // Best (total, last directory deleted, total before it)
+        let width = to_free as usize;
+        let mut best: Option<(u64, usize, usize)> = None;
+        sweep(&sizes, &end, width, order.len(), |k, reachable| {
+            let from = width.saturating_sub(sizes[k] as usize);
+            if let Some(before) = first_set(reachable, from) {
+                let total = before as u64 + sizes[k];
+                if sizes[k] > 0 && best.is_none_or(|(t, _, _)| total < t) {
+                    best = Some((total, k, before));
+                }
+            }
+            false
+        });
+        let (_, last, mut total) = best.ok_or("No directory frees enough space")?;
+
+        // Each total below `to_free` came from deleting some directory that ends before
+        // the one deleted after it, so find one per sweep working backwards
+        let mut dirs = vec![order[last]];
+        let mut limit = last;
+        while total > 0 {
+            sweep(&sizes, &end, width, limit, |k, reachable| {
+                let size = sizes[k] as usize;
+                let found = end[k] <= limit
+                    && size > 0
+                    && size <= total
+                    && reachable[(total - size) / 64] & 1 << ((total - size) % 64) != 0;
+                if found {
+                    dirs.push(order[k]);
+                    total -= size;
+                    limit = k;
+                }
+                found
+            });
+        }
+        dirs.sort_by_key(|&id| fs.path(id));
+        Ok(dirs)
     }
-    root
 }
 
-fn dir_sizes(input: &str) -> Vec<u32> {
-    let cmds = input
-        .split('$')
-        .flat_map(Command::try_from)
-        .collect::<Vec<Command>>();
+// Subset sum over directories in pre-order, each either deleted, skipping past its
+// subtree to `end[k]`, or kept and descended into. Calls `visit(k, reachable)` for
+// each k below `limit` with the totals below `width` that deletions before k can
+// reach, stopping early if it returns true. The totals only grow from one position
+// to the next, so one bitset is carried along, plus one per pending subtree end.
+fn sweep(
+    sizes: &[u64],
+    end: &[usize],
+    width: usize,
+    limit: usize,
+    mut visit: impl FnMut(usize, &[u64]) -> bool,
+) {
+    let words = width.div_ceil(64);
+    let mut reachable = vec![0u64; words];
+    reachable[0] = 1;
+    let mut pending: BTreeMap<usize, Vec<u64>> = BTreeMap::new();
+    for k in 0..limit {
+        if let Some(bits) = pending.remove(&k) {
+            reachable.iter_mut().zip(bits).for_each(|(r, b)| *r |= b);
+        }
+        if visit(k, &reachable) {
+            return;
+        }
+        let size = sizes[k] as usize;
+        if size > 0 && size < width {
+            let deleted = pending.entry(end[k]).or_insert_with(|| vec![0; words]);
+            or_shifted(deleted, &reachable, size, width - 1);
+        }
+    }
+}
+
+// Lowest set bit at or after `from`
+fn first_set(bits: &[u64], from: usize) -> Option<usize> {
+    let mut i = from / 64;
+    let mut word = bits.get(i)? & u64::MAX << (from % 64);
+    while word == 0 {
+        i += 1;
+        word = *bits.get(i)?;
+    }
+    Some(i * 64 + word.trailing_zeros() as usize)
+}
 
-    let files = build_file_hierarchy(cmds);
-    files.directory_sizes()
+// Sets bit t + shift of `dst` for every bit t of `src`, ignoring totals beyond `bound`
+fn or_shifted(dst: &mut [u64], src: &[u64], shift: usize, bound: usize) {
+    let (words, bits) = (shift / 64, shift % 64);
+    for i in (words..dst.len()).rev() {
+        let low = src[i - words] << bits;
+        let carry = match (bits, i > words) {
+            (0, _) | (_, false) => 0,
+            _ => src[i - words - 1] >> (64 - bits),
+        };
+        dst[i] |= low | carry;
+    }
+    if let Some(last) = dst.last_mut() {
+        let used = bound % 64 + 1;
+        if used < 64 {
+            *last &= (1 << used) - 1;
+        }
+    }
 }
 
-fn part1(input: &str) -> u32 {
-    dir_sizes(input).iter().filter(|s| **s < 100000).sum()
+// Runs one query: du [-a] [PATH], find [PATH] [OPTIONS], largest files|dirs [N], json [PATH]
+// or cleanup dir|set [DISK REQUIRED]
+fn query(fs: &FileSystem, line: &str) -> Result<String, &'static str> {
+    let mut args = line.split_whitespace().peekable();
+    let command = args.next().ok_or("Empty query")?;
+    let all = command == "du" && args.next_if_eq(&"-a").is_some();
+    let path = match args.peek() {
+        Some(p) if !matches!(command, "largest" | "cleanup") && !p.starts_with('-') => {
+            args.next().unwrap()
+        }
+        _ => "/",
+    };
+    let id = fs
+        .resolve(FileSystem::ROOT, path)
+        .ok_or("No such file or directory")?;
+    match command {
+        "du" if args.peek().is_none() => Ok(du(fs, id, all)),
+        "find" => Ok(find(fs, id, &Predicate::parse_all(args)?).join("\n")),
+        "largest" => {
+            let dirs = match args.next() {
+                Some("files") => false,
+                Some("dirs") => true,
+                _ => return Err("Expected files or dirs after largest"),
+            };
+            let n = args
+                .next()
+                .map_or(Ok(10), |n| n.parse().map_err(|_| "Invalid count"))?;
+            Ok(largest(fs, n, dirs)
+                .into_iter()
+                .map(|id| format!("{:>7}  {}", human_size(fs.size(id)), fs.path(id)))
+                .collect::<Vec<String>>()
+                .join("\n"))
+        }
+        "cleanup" => {
+            let mode = args.next();
+            let mut size = || args.next().map(|n| n.parse().map_err(|_| "Invalid size"));
+            let cleanup = Cleanup::new(
+                size().unwrap_or(Ok(70000000))?,
+                size().unwrap_or(Ok(30000000))?,
+            );
+            let dirs = match mode {
+                Some("dir") => cleanup.smallest_dir(fs)?.into_iter().collect(),
+                Some("set") => cleanup.smallest_set(fs)?,
+                _ => return Err("Expected dir or set after cleanup"),
+            };
+            let total = dirs.iter().map(|&id| fs.size(id)).sum::<u64>();
+            let mut lines = dirs
+                .into_iter()
+                .map(|id| format!("{:>7}  {}", human_size(fs.size(id)), fs.path(id)))
+                .collect::<Vec<String>>();
+            lines.push(format!(
+                "Frees {} of {} needed",
+                total,
+                cleanup.to_free(fs)?
+            ));
+            Ok(lines.join("\n"))
+        }
+        "json" if args.peek().is_none() => {
+            let mut out = String::new();
+            json(fs, id, 0, &mut out);
+            Ok(out)
+        }
+        _ => Err("Unknown query"),
+    }
 }
 
-fn part2(input: &str) -> u32 {
-    let sizes = dir_sizes(input);
+fn part1(input: &str) -> u64 {
+    let fs = FileSystem::from_transcript(input).expect("Invalid input");
+    fs.directories()
+        .map(|id| fs.size(id))
+        .filter(|&s| s < 100000)
+        .sum()
+}
 
-    let total_used = sizes.iter().last().expect("No directories");
-    let disk_space = 70000000;
-    let to_free = 30000000 - (disk_space - total_used);
-    *sizes
-        .iter()
-        .find(|s| **s >= to_free)
-        .expect("No suitable directory found")
+fn part2(input: &str) -> u64 {
+    let fs = FileSystem::from_transcript(input).expect("Invalid input");
+    Cleanup::new(70000000, 30000000)
+        .smallest_dir(&fs)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .map_or(0, |id| fs.size(id))
 }
 
 fn main() {
     let input = fs::read_to_string("inputs/day07.txt").expect("Could not read input file");
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if !args.is_empty() {
+        let fs = FileSystem::from_transcript(&input).unwrap_or_else(|e| panic!("{e}"));
+        if args[0] != "--interactive" {
+            match query(&fs, &args.join(" ")) {
+                Ok(output) => println!("{output}"),
+                Err(e) => eprintln!("{e}"),
+            }
+            return;
+        }
+        print!("> ");
+        io::stdout().flush().expect("Could not write output");
+        for line in io::stdin().lock().lines() {
+            let line = line.expect("Could not read query");
+            match query(&fs, &line) {
+                Ok(output) => println!("{output}"),
+                Err(e) => println!("{e}"),
+            }
+            print!("> ");
+            io::stdout().flush().expect("Could not write output");
+        }
+        return;
+    }
     let part1 = part1(&input);
     let part2 = part2(&input);
     println!("Part 1: {part1}");
@@ -186,4 +733,294 @@ $ ls
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 24933642);
     }
+    #[test]
+    fn test_paths_and_sizes() {
+        let fs = FileSystem::from_transcript(TEST_INPUT).unwrap();
+        let e = fs.resolve(FileSystem::ROOT, "/a/e").unwrap();
+        assert_eq!(fs.path(e), "/a/e");
+        assert_eq!(fs.size(e), 584);
+        assert_eq!(
+            fs.resolve(e, "../../d/k").map(|id| fs.size(id)),
+            Some(7214296)
+        );
+        assert_eq!(fs.path(FileSystem::ROOT), "/");
+        assert_eq!(fs.size(FileSystem::ROOT), 48381165);
+        assert_eq!(fs.directories().count(), 4);
+        assert_eq!(fs.nodes.len(), 14);
+        assert!(fs.resolve(FileSystem::ROOT, "/a/f/x").is_none());
+    }
+    #[test]
+    fn test_repeated_listing() {
+        let twice =
+            format!("{TEST_INPUT}\n$ cd /\n$ ls\ndir a\n14848514 b.txt\n$ cd a\n$ ls\n29116 f");
+        let fs = FileSystem::from_transcript(&twice).unwrap();
+        assert_eq!(fs.size(FileSystem::ROOT), 48381165);
+        assert_eq!(fs.nodes.len(), 14);
+        assert_eq!(part1(&twice), 95437);
+    }
+    #[test]
+    fn test_empty_directories() {
+        let fs = FileSystem::from_transcript("$ cd /\n$ ls\ndir empty\n10 file").unwrap();
+        let empty = fs.resolve(FileSystem::ROOT, "empty").unwrap();
+        assert!(fs.is_dir(empty));
+        assert_eq!(fs.size(empty), 0);
+        assert_eq!(fs.directories().count(), 2);
+    }
+    #[test]
+    fn test_cd_anywhere() {
+        let input = "$ ls\ndir a\n$ cd a\n$ ls\ndir b\n$ cd b\n$ cd /\n$ cd a/b\n$ ls\n5 x\n$ cd /a\n$ ls\n3 y";
+        let fs = FileSystem::from_transcript(input).unwrap();
+        assert_eq!(
+            fs.resolve(FileSystem::ROOT, "/a/b/x").map(|id| fs.path(id)),
+            Some("/a/b/x".to_string())
+        );
+        assert_eq!(fs.size(FileSystem::ROOT), 8);
+    }
+    #[test]
+    fn test_errors() {
+        let error = |input| FileSystem::from_transcript(input).err();
+        assert_eq!(
+            error("$ ls\n10 a\n$ ls\n20 a"),
+            Some(FsError::Conflict {
+                line: 4,
+                path: "/a".to_string()
+            })
+        );
+        assert_eq!(
+            error("$ ls\n10 a\ndir a"),
+            Some(FsError::Conflict {
+                line: 3,
+                path: "/a".to_string()
+            })
+        );
+        assert_eq!(
+            error("$ cd /\n$ cd x"),
+            Some(FsError::NoSuchDirectory {
+                line: 2,
+                path: "x".to_string()
+            })
+        );
+        assert_eq!(
+            error("$ ls\n10 a\n$ cd a"),
+            Some(FsError::NoSuchDirectory {
+                line: 3,
+                path: "a".to_string()
+            })
+        );
+        assert_eq!(
+            error("$ cd /\n10 a"),
+            Some(FsError::UnexpectedOutput { line: 2 })
+        );
+        assert_eq!(
+            error("$ rm -rf /"),
+            Some(FsError::UnknownCommand { line: 1 })
+        );
+        assert_eq!(
+            error("$ ls\nten a"),
+            Some(FsError::InvalidEntry { line: 2 })
+        );
+        assert_eq!(
+            error("$ ls\n10 ../a"),
+            Some(FsError::InvalidEntry { line: 2 })
+        );
+    }
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(584), "584");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(94853), "92.6K");
+        assert_eq!(human_size(48381165), "46.1M");
+        assert_eq!(human_size(5 << 40), "5.0T");
+        assert_eq!(human_size(5 << 50), "5120.0T");
+    }
+    #[test]
+    fn test_glob() {
+        assert!(glob_match("*.txt", "b.txt"));
+        assert!(glob_match("d.*", "d.log"));
+        assert!(glob_match("?", "k"));
+        assert!(glob_match("*a*b*", "xaybzb"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("*.txt", "b.txt.bak"));
+        assert!(!glob_match("?", "ab"));
+        assert!(!glob_match("a*c", "abcd"));
+    }
+    #[test]
+    fn test_du() {
+        let fs = FileSystem::from_transcript(TEST_INPUT).unwrap();
+        assert_eq!(
+            query(&fs, "du").unwrap(),
+            "  46.1M  /\n  92.6K    a/\n    584      e/\n  23.8M    d/"
+        );
+        assert_eq!(
+            query(&fs, "du -a /a").unwrap(),
+            "  92.6K  a/\n    584    e/\n    584      i\n  28.4K    f\n   2.5K    g\n  61.1K    h.lst"
+        );
+    }
+    #[test]
+    fn test_find() {
+        let fs = FileSystem::from_transcript(TEST_INPUT).unwrap();
+        assert_eq!(
+            query(&fs, "find -name d*").unwrap(),
+            "/d\n/d/d.ext\n/d/d.log"
+        );
+        assert_eq!(
+            query(&fs, "find /d -type f -size +6000000").unwrap(),
+            "/d/d.log\n/d/k"
+        );
+        assert_eq!(
+            query(&fs, "find / -type d -size -100000").unwrap(),
+            "/a\n/a/e"
+        );
+        assert_eq!(query(&fs, "find -size 584").unwrap(), "/a/e\n/a/e/i");
+        assert_eq!(query(&fs, "find /a/e/i").unwrap(), "/a/e/i");
+    }
+    #[test]
+    fn test_largest() {
+        let fs = FileSystem::from_transcript(TEST_INPUT).unwrap();
+        assert_eq!(
+            query(&fs, "largest files 2").unwrap(),
+            "  14.2M  /b.txt\n   8.1M  /c.dat"
+        );
+        assert_eq!(query(&fs, "largest dirs 1").unwrap(), "  46.1M  /");
+        assert_eq!(query(&fs, "largest dirs").unwrap().lines().count(), 4);
+    }
+    #[test]
+    fn test_json() {
+        let fs = FileSystem::from_transcript("$ ls\ndir a\n10 \"b\"\n$ cd a\n$ ls\ndir c").unwrap();
+        assert_eq!(
+            query(&fs, "json").unwrap(),
+            r#"{"name": "/", "type": "dir", "size": 10, "children": [
+  {"name": "\"b\"", "type": "file", "size": 10},
+  {"name": "a", "type": "dir", "size": 0, "children": [
+    {"name": "c", "type": "dir", "size": 0, "children": []}
+  ]}
+]}"#
+        );
+    }
+    #[test]
+    fn test_query_errors() {
+        let fs = FileSystem::from_transcript(TEST_INPUT).unwrap();
+        assert_eq!(query(&fs, ""), Err("Empty query"));
+        assert_eq!(query(&fs, "du /x"), Err("No such file or directory"));
+        assert_eq!(query(&fs, "find -size big"), Err("Invalid size for -size"));
+        assert_eq!(query(&fs, "find -size é"), Err("Invalid size for -size"));
+        assert_eq!(query(&fs, "find -size +é"), Err("Invalid size for -size"));
+        assert_eq!(
+            query(&fs, "find -type x"),
+            Err("Expected f or d after -type")
+        );
+        assert_eq!(
+            query(&fs, "find -name"),
+            Err("Missing value for find option")
+        );
+        assert_eq!(
+            query(&fs, "largest 5"),
+            Err("Expected files or dirs after largest")
+        );
+        assert_eq!(query(&fs, "tree"), Err("Unknown query"));
+    }
+    #[test]
+    fn test_cleanup() {
+        // a holds 4 and b (6), c holds 5, so 15 is used in total
+        let fs = FileSystem::from_transcript(
+            "$ ls\ndir a\ndir c\n$ cd a\n$ ls\n4 x\ndir b\n$ cd b\n$ ls\n6 y\n$ cd /c\n$ ls\n5 z",
+        )
+        .unwrap();
+        let paths = |plan: Result<Vec<usize>, &'static str>| {
+            plan.map(|dirs| dirs.iter().map(|&id| fs.path(id)).collect::<Vec<String>>())
+        };
+        let cleanup = Cleanup::new(100, 96);
+        assert_eq!(cleanup.to_free(&fs), Ok(11));
+        let path = |dir: Result<Option<usize>, &'static str>| dir.map(|d| d.map(|id| fs.path(id)));
+        assert_eq!(path(cleanup.smallest_dir(&fs)), Ok(Some("/".to_string())));
+        assert_eq!(
+            paths(cleanup.smallest_set(&fs)),
+            Ok(vec!["/a/b".to_string(), "/c".to_string()])
+        );
+        let cleanup = Cleanup::new(100, 90);
+        assert_eq!(paths(cleanup.smallest_set(&fs)), Ok(vec!["/c".to_string()]));
+        assert_eq!(path(cleanup.smallest_dir(&fs)), Ok(Some("/c".to_string())));
+        assert_eq!(Cleanup::new(100, 80).smallest_dir(&fs), Ok(None));
+        // Deleting everything only frees 15
+        let cleanup = Cleanup::new(100, 101);
+        assert_eq!(
+            cleanup.smallest_set(&fs),
+            Err("No directory frees enough space")
+        );
+        assert_eq!(Cleanup::new(100, 80).smallest_set(&fs), Ok(vec![]));
+        assert_eq!(
+            Cleanup::new(10, 5).to_free(&fs),
+            Err("Filesystem is larger than the disk")
+        );
+    }
+    #[test]
+    fn test_cleanup_query() {
+        let fs = FileSystem::from_transcript(TEST_INPUT).unwrap();
+        assert_eq!(
+            query(&fs, "cleanup dir").unwrap(),
+            "  23.8M  /d\nFrees 24933642 of 8381165 needed"
+        );
+        assert_eq!(
+            query(&fs, "cleanup set 48400000 100000").unwrap(),
+            "  92.6K  /a\nFrees 94853 of 81165 needed"
+        );
+        assert_eq!(
+            query(&fs, "cleanup set 50000000 1000").unwrap(),
+            "Frees 0 of 0 needed"
+        );
+        assert_eq!(
+            query(&fs, "cleanup"),
+            Err("Expected dir or set after cleanup")
+        );
+        assert_eq!(query(&fs, "cleanup dir lots"), Err("Invalid size"));
+    }
+    #[test]
+    fn test_cleanup_set_skips_nested() {
+        // a holds 1 and b (9), c holds 5 and d holds 4, so 19 is used in total
+        let fs = FileSystem::from_transcript(
+            "$ ls\ndir a\ndir c\ndir d\n$ cd a\n$ ls\n1 x\ndir b\n$ cd b\n$ ls\n9 y\n\
+             $ cd /c\n$ ls\n5 z\n$ cd /d\n$ ls\n4 w",
+        )
+        .unwrap();
+        let set = |to_free: u64| {
+            let dirs = Cleanup::new(19, to_free).smallest_set(&fs);
+            dirs.map(|dirs| dirs.iter().map(|&id| fs.path(id)).collect::<Vec<String>>())
+        };
+        // b and d beat any set with a in it, without counting b twice
+        assert_eq!(set(13), Ok(vec!["/a/b".to_string(), "/d".to_string()]));
+        assert_eq!(
+            set(18),
+            Ok(vec!["/a/b".to_string(), "/c".to_string(), "/d".to_string()])
+        );
+        assert_eq!(set(19), Ok(vec!["/".to_string()]));
+        assert_eq!(set(20), Err("No directory frees enough space"));
+    }
+    #[test]
+    fn test_cleanup_set_after_deep_subtree() {
+        // p holds q holds r, then s follows at the top level
+        let fs = FileSystem::from_transcript(
+            "$ ls\ndir p\ndir s\n$ cd p\n$ ls\n1 x\ndir q\n$ cd q\n$ ls\n2 y\ndir r\n\
+             $ cd r\n$ ls\n3 z\n$ cd /s\n$ ls\n4 w",
+        )
+        .unwrap();
+        let set = |to_free: u64| {
+            let dirs = Cleanup::new(10, to_free).smallest_set(&fs).unwrap();
+            dirs.iter().map(|&id| fs.path(id)).collect::<Vec<String>>()
+        };
+        assert_eq!(set(7), vec!["/p/q/r", "/s"]);
+        assert_eq!(set(9), vec!["/p/q", "/s"]);
+        assert_eq!(set(6), vec!["/p"]);
+    }
+    #[test]
+    fn test_cleanup_set_beyond_used_space() {
+        let fs = FileSystem::from_transcript(TEST_INPUT).unwrap();
+        assert_eq!(
+            query(&fs, "cleanup set 48381165 100000000000000"),
+            Err("No directory frees enough space")
+        );
+        assert_eq!(
+            query(&fs, "cleanup dir 48381165 100000000000000"),
+            Err("No directory frees enough space")
+        );
+    }
 }
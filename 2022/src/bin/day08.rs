@@ -1,129 +1,209 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs,
-};
-
-fn parse_input<T: From<u8>>(input: &str) -> (Vec<T>, usize) {
-    let x_len = input.lines().next().expect("Invalid input").len();
-    let vec = input
-        .lines()
-        .flat_map(str::as_bytes)
-        .map(|c| T::from(c - b'0'))
-        .collect::<Vec<T>>();
-    (vec, x_len)
-}
+use aoc::sight::{HeightMap, ALL_DIRECTIONS, ORTHOGONAL};
+use std::{cmp::Reverse, env, fmt::Display, fs};
 
-fn row_iter_indexed<T>(
-    vec: &[T],
-    row_length: usize,
-) -> impl Iterator<Item = impl Iterator<Item = ((usize, usize), &T)> + DoubleEndedIterator> + Clone
-{
-    vec.chunks(row_length)
-        .enumerate()
-        .map(|(j, row)| row.iter().enumerate().map(move |(i, v)| ((i, j), v)))
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    Empty,
+    InvalidHeight {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
 }
 
-fn column_iter_indexed<T>(
-    vec: &[T],
-    row_length: usize,
-) -> impl Iterator<Item = impl Iterator<Item = ((usize, usize), &T)> + DoubleEndedIterator> + Clone
-{
-    (0..row_length).map(move |i| {
-        vec.iter()
-            .skip(i)
-            .step_by(row_length)
-            .enumerate()
-            .map(move |(j, v)| ((i, j), v))
-    })
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "No trees in input"),
+            Self::InvalidHeight {
+                line,
+                column,
+                found,
+            } => write!(
+                f,
+                "Invalid height {:?} on line {}, column {}",
+                found, line, column
+            ),
+            Self::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Line {} has {} trees, expected {}",
+                line, found, expected
+            ),
+        }
+    }
 }
 
-fn find_visible<'a>(
-    row: impl Iterator<Item = ((usize, usize), &'a i16)> + 'a,
-) -> Vec<(usize, usize)> {
-    let mut visible = Vec::<(usize, usize)>::new();
-    let mut max = -1;
-    for ((i, j), tree) in row {
-        if tree > &max {
-            max = *tree;
-            visible.push((i, j));
+fn parse_input(input: &str) -> Result<HeightMap<u8>, ParseError> {
+    let mut heights = vec![];
+    let mut width = None;
+    for (i, line) in input.lines().enumerate() {
+        // Columns count from the start of the line as written
+        let indent = line.chars().take_while(|c| c.is_whitespace()).count();
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        for (j, c) in line.chars().enumerate() {
+            let height = c.to_digit(10).ok_or(ParseError::InvalidHeight {
+                line: i + 1,
+                column: indent + j + 1,
+                found: c,
+            })?;
+            heights.push(height as u8);
+        }
+        let found = line.chars().count();
+        match width {
+            Some(expected) if expected != found => {
+                return Err(ParseError::RaggedRow {
+                    line: i + 1,
+                    expected,
+                    found,
+                })
+            }
+            _ => width = Some(found),
         }
     }
-    visible
+    HeightMap::new(heights, width.ok_or(ParseError::Empty)?).ok_or(ParseError::Empty)
 }
 
-fn part1(input: &str) -> usize {
-    let (parsed, x_len) = parse_input::<i16>(input);
-    let mut visible = HashSet::<(usize, usize)>::new();
+fn visible_count(map: &HeightMap<u8>, directions: &[(isize, isize)]) -> usize {
+    map.visible(directions).into_iter().filter(|&v| v).count()
+}
 
-    let rows = row_iter_indexed(&parsed, x_len);
-    let columns = column_iter_indexed(&parsed, x_len);
+fn best_score(map: &HeightMap<u8>, directions: &[(isize, isize)]) -> u64 {
+    map.scenic_scores(directions).into_iter().max().unwrap_or(0)
+}
+
+// Index of the tree with the highest scenic score, the first one on ties
+fn best_tree(scores: &[u64]) -> Option<usize> {
+    scores
+        .iter()
+        .enumerate()
+        .max_by_key(|&(i, &score)| (score, Reverse(i)))
+        .map(|(i, _)| i)
+}
 
-    let right = rows.clone().flat_map(find_visible);
-    let left = rows.map(Iterator::rev).flat_map(find_visible);
-    let down = columns.clone().flat_map(find_visible);
-    let up = columns.map(Iterator::rev).flat_map(find_visible);
+// '#' for visible trees and '.' for hidden ones, with the best tree as '*'
+fn render_mask(map: &HeightMap<u8>, visible: &[bool], best: Option<usize>) -> String {
+    visible
+        .chunks(map.width())
+        .enumerate()
+        .map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(x, &v)| match v {
+                    _ if best == Some(map.index(x, y)) => '*',
+                    true => '#',
+                    false => '.',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
 
-    visible.extend(right);
-    visible.extend(left);
-    visible.extend(up);
-    visible.extend(down);
+// Grey level of a score, on a log scale since a few trees score far above the rest
+fn shade(score: u64, max: u64) -> u8 {
+    if max == 0 {
+        return 0;
+    }
+    (255.0 * (score as f64).ln_1p() / (max as f64).ln_1p()).round() as u8
+}
 
-    visible.len()
+// Scenic scores as a plain (ASCII) PGM image, one pixel per tree
+fn render_pgm(map: &HeightMap<u8>, scores: &[u64]) -> String {
+    let max = scores.iter().copied().max().unwrap_or(0);
+    let rows = scores.chunks(map.width()).map(|row| {
+        row.iter()
+            .map(|&s| shade(s, max).to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    });
+    format!("P2\n{} {}\n255\n", map.width(), map.height())
+        + &rows.collect::<Vec<String>>().join("\n")
+        + "\n"
 }
 
-fn update_scores(scores: &mut [usize], tree_size: usize) -> usize {
-    // Updates the accumulated scores and returns the score for the tree
-    let score = scores[tree_size];
-    scores
-        .iter_mut()
+// Like the PGM image, but in colour so the best tree can be marked in red
+fn render_ppm(map: &HeightMap<u8>, scores: &[u64], best: Option<usize>) -> String {
+    let max = scores.iter().copied().max().unwrap_or(0);
+    let pixels = scores
+        .iter()
         .enumerate()
-        .for_each(|(i, s)| if i <= tree_size { *s = 1 } else { *s += 1 });
-    score
+        .map(|(i, &s)| match shade(s, max) {
+            _ if best == Some(i) => "255 0 0".to_string(),
+            grey => format!("{grey} {grey} {grey}"),
+        })
+        .collect::<Vec<String>>();
+    let rows = pixels.chunks(map.width()).map(|row| row.join("  "));
+    format!("P3\n{} {}\n255\n", map.width(), map.height())
+        + &rows.collect::<Vec<String>>().join("\n")
+        + "\n"
 }
 
-fn find_scores<'a>(
-    row: impl Iterator<Item = ((usize, usize), &'a usize)> + 'a,
-) -> Vec<((usize, usize), usize)> {
-    let mut scores_acc = vec![0usize; 10];
-    let mut scores = Vec::<((usize, usize), usize)>::new();
-    for (coords, tree) in row {
-        let score = update_scores(&mut scores_acc, *tree);
-        scores.push((coords, score));
-    }
-    scores
+fn render_csv(map: &HeightMap<u8>, visible: &[bool], scores: &[u64]) -> String {
+    let rows = map.heights().iter().enumerate().map(|(i, height)| {
+        let (x, y) = map.position(i);
+        format!("{},{},{},{},{}", x, y, height, visible[i], scores[i])
+    });
+    std::iter::once("x,y,height,visible,score".to_string())
+        .chain(rows)
+        .collect::<Vec<String>>()
+        .join("\n")
 }
 
-fn part2(input: &str) -> usize {
-    let (parsed, x_len) = parse_input::<usize>(input);
-    let mut trees = HashMap::<(usize, usize), usize>::new();
-
-    let rows = row_iter_indexed(&parsed, x_len);
-    let columns = column_iter_indexed(&parsed, x_len);
-
-    //Right
-    rows.clone()
-        .flat_map(find_scores)
-        .for_each(|(k, score)| *trees.entry(k).or_insert(1) *= score);
-    // Left
-    rows.map(Iterator::rev)
-        .flat_map(find_scores)
-        .for_each(|(k, score)| *trees.entry(k).or_insert(1) *= score);
-    //Down
-    columns
-        .clone()
-        .flat_map(find_scores)
-        .for_each(|(k, score)| *trees.entry(k).or_insert(1) *= score);
-    // Up
-    columns
-        .map(Iterator::rev)
-        .flat_map(find_scores)
-        .for_each(|(k, score)| *trees.entry(k).or_insert(1) *= score);
-
-    *trees.iter().max_by(|a, b| a.1.cmp(b.1)).unwrap().1
+fn part1(input: &str) -> usize {
+    let map = parse_input(input).unwrap_or_else(|e| panic!("{e}"));
+    visible_count(&map, &ORTHOGONAL)
+}
+
+fn part2(input: &str) -> u64 {
+    let map = parse_input(input).unwrap_or_else(|e| panic!("{e}"));
+    best_score(&map, &ORTHOGONAL)
 }
 
 fn main() {
     let input = fs::read_to_string("inputs/day08.txt").expect("Could not read input file");
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if !args.is_empty() {
+        let map = parse_input(&input).unwrap_or_else(|e| panic!("{e}"));
+        let directions = match args.iter().any(|a| a == "--diagonal") {
+            true => &ALL_DIRECTIONS[..],
+            false => &ORTHOGONAL[..],
+        };
+        let visible = map.visible(directions);
+        let scores = map.scenic_scores(directions);
+        let best = best_tree(&scores);
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut write = |contents: String| {
+                let path = args.next().expect("Expected an output path");
+                fs::write(path, contents).expect("Could not write output file");
+            };
+            match arg.as_str() {
+                "--mask" => println!("{}", render_mask(&map, &visible, best)),
+                "--pgm" => write(render_pgm(&map, &scores)),
+                "--ppm" => write(render_ppm(&map, &scores, best)),
+                "--csv" => write(render_csv(&map, &visible, &scores)),
+                _ => {}
+            }
+        }
+        println!("Visible: {}", visible.iter().filter(|&&v| v).count());
+        if let Some(best) = best {
+            let (x, y) = map.position(best);
+            println!("Best tree: ({x}, {y}) scoring {}", scores[best]);
+        }
+        return;
+    }
     let part1 = part1(&input);
     let part2 = part2(&input);
     println!("Part 1: {part1}");
@@ -149,4 +229,138 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 8);
     }
+    // Walks every ray from every tree, for comparing against the sweeps
+    fn brute_force(map: &HeightMap<u8>, directions: &[(isize, isize)]) -> (Vec<bool>, Vec<u64>) {
+        let (w, h) = (map.width() as isize, map.height() as isize);
+        let mut visible = vec![false; map.heights().len()];
+        let mut scores = vec![1; map.heights().len()];
+        for (i, &height) in map.heights().iter().enumerate() {
+            let (x, y) = map.position(i);
+            for &(dx, dy) in directions {
+                let (mut cx, mut cy, mut seen) = (x as isize + dx, y as isize + dy, 0);
+                let mut blocked = false;
+                while (0..w).contains(&cx) && (0..h).contains(&cy) {
+                    seen += 1;
+                    if map.heights()[map.index(cx as usize, cy as usize)] >= height {
+                        blocked = true;
+                        break;
+                    }
+                    cx += dx;
+                    cy += dy;
+                }
+                visible[i] |= !blocked;
+                scores[i] *= seen;
+            }
+        }
+        (visible, scores)
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let map = parse_input(TEST_INPUT).unwrap();
+        let (visible, scores) = brute_force(&map, &ALL_DIRECTIONS);
+        assert_eq!(map.visible(&ALL_DIRECTIONS), visible);
+        assert_eq!(map.scenic_scores(&ALL_DIRECTIONS), scores);
+        // The best tree of part 2 also sees two trees up and right along the diagonal
+        assert_eq!(map.scenic_scores(&ALL_DIRECTIONS)[map.index(2, 3)], 8 * 2);
+    }
+    #[test]
+    fn test_ties_block() {
+        // The inner trees are hidden by trees of their own height or taller
+        let map = parse_input("2222\n2112\n2222").unwrap();
+        for directions in [&ORTHOGONAL[..], &ALL_DIRECTIONS[..]] {
+            assert_eq!(visible_count(&map, directions), 10);
+            assert_eq!(map.scenic_scores(directions)[map.index(1, 1)], 1);
+            assert_eq!(
+                map.scenic_scores(directions),
+                brute_force(&map, directions).1
+            );
+        }
+    }
+    #[test]
+    fn test_tall_tree_sees_over() {
+        let map = parse_input("11111\n11111\n11911\n11111\n11111").unwrap();
+        assert_eq!(visible_count(&map, &ORTHOGONAL), 17);
+        assert_eq!(visible_count(&map, &ALL_DIRECTIONS), 17);
+        // Two trees in each direction from the centre, one from its neighbours
+        assert_eq!(map.scenic_scores(&ORTHOGONAL)[map.index(2, 2)], 16);
+        assert_eq!(map.scenic_scores(&ALL_DIRECTIONS)[map.index(2, 2)], 256);
+        assert_eq!(map.scenic_scores(&ORTHOGONAL)[map.index(1, 2)], 1);
+        assert_eq!(best_score(&map, &ALL_DIRECTIONS), 256);
+    }
+    #[test]
+    fn test_thin_maps() {
+        let single = parse_input("5").unwrap();
+        assert_eq!(single.visible(&ALL_DIRECTIONS), vec![true]);
+        assert_eq!(single.scenic_scores(&ALL_DIRECTIONS), vec![0]);
+        // Every tree of a single row or column is on the edge, seeing nothing to one side
+        for input in ["13231", "1\n3\n2\n3\n1"] {
+            let map = parse_input(input).unwrap();
+            assert_eq!(map.visible(&ORTHOGONAL), vec![true; 5]);
+            assert_eq!(map.scenic_scores(&ORTHOGONAL), vec![0; 5]);
+        }
+    }
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_input("123\n1a3"),
+            Err(ParseError::InvalidHeight {
+                line: 2,
+                column: 2,
+                found: 'a'
+            })
+        );
+        assert_eq!(
+            parse_input("123\n12"),
+            Err(ParseError::RaggedRow {
+                line: 2,
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            parse_input("  123\n\t 1a3"),
+            Err(ParseError::InvalidHeight {
+                line: 2,
+                column: 4,
+                found: 'a'
+            })
+        );
+        assert_eq!(parse_input("\n\n"), Err(ParseError::Empty));
+    }
+    #[test]
+    fn test_renderers() {
+        let map = parse_input(TEST_INPUT).unwrap();
+        let visible = map.visible(&ORTHOGONAL);
+        let scores = map.scenic_scores(&ORTHOGONAL);
+        let best = best_tree(&scores);
+        assert_eq!(best, Some(map.index(2, 3)));
+        assert_eq!(
+            render_mask(&map, &visible, best),
+            "#####\n###.#\n##.##\n#.*.#\n#####"
+        );
+        let pgm = render_pgm(&map, &scores);
+        assert_eq!(
+            pgm,
+            "P2\n5 5\n255\n0 0 0 0 0\n0 80 187 80 0\n0 226 80 127 0\n0 80 255 161 0\n0 0 0 0 0\n"
+        );
+        let ppm = render_ppm(&map, &scores, best);
+        assert_eq!(
+            ppm.lines().nth(6),
+            Some("0 0 0  80 80 80  255 0 0  161 161 161  0 0 0")
+        );
+        let csv = render_csv(&map, &visible, &scores);
+        assert_eq!(csv.lines().next(), Some("x,y,height,visible,score"));
+        assert_eq!(csv.lines().nth(1 + map.index(2, 3)), Some("2,3,5,true,8"));
+        assert_eq!(csv.lines().count(), 26);
+    }
+    #[test]
+    fn test_shade() {
+        assert_eq!(shade(0, 0), 0);
+        assert_eq!(shade(0, 8), 0);
+        assert_eq!(shade(8, 8), 255);
+        assert_eq!(shade(1, 8), 80);
+        assert_eq!(best_tree(&[3, 5, 5, 1]), Some(1));
+        assert_eq!(best_tree(&[]), None);
+    }
 }
@@ -1,119 +1,499 @@
-use std::{collections::HashSet, fs, ops, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufRead, BufWriter, Write},
+    ops, process,
+    str::FromStr,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
 
-#[derive(Clone, Eq, Hash, PartialEq)]
-struct Pos(i32, i32);
+// Position in N dimensions, the first two being x (right) and y (down)
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Pos<const N: usize>([i32; N]);
 
-impl ops::Add<&Pos> for &Pos {
-    type Output = Pos;
-    fn add(self, rhs: &Pos) -> Pos {
-        Pos(self.0 + rhs.0, self.1 + rhs.1)
-    }
+impl<const N: usize> Pos<N> {
+    const ORIGIN: Self = Self([0; N]);
 }
 
-impl ops::Sub<&Pos> for &Pos {
-    type Output = Pos;
-    fn sub(self, rhs: &Pos) -> Pos {
-        Pos(self.0 - rhs.0, self.1 - rhs.1)
+impl<const N: usize> ops::Add<&Pos<N>> for &Pos<N> {
+    type Output = Pos<N>;
+    fn add(self, rhs: &Pos<N>) -> Pos<N> {
+        Pos(std::array::from_fn(|i| self.0[i] + rhs.0[i]))
     }
 }
 
-impl ops::Add<&Dir> for &Pos {
-    type Output = Pos;
-    fn add(self, rhs: &Dir) -> Pos {
-        match rhs {
-            Dir::R => Pos(self.0 + 1, self.1),
-            Dir::L => Pos(self.0 - 1, self.1),
-            Dir::D => Pos(self.0, self.1 + 1),
-            Dir::U => Pos(self.0, self.1 - 1),
-        }
+impl<const N: usize> ops::Sub<&Pos<N>> for &Pos<N> {
+    type Output = Pos<N>;
+    fn sub(self, rhs: &Pos<N>) -> Pos<N> {
+        Pos(std::array::from_fn(|i| self.0[i] - rhs.0[i]))
     }
 }
 
-enum Dir {
-    R,
-    L,
-    D,
-    U,
+impl<const N: usize> ops::Add<&Dir<N>> for &Pos<N> {
+    type Output = Pos<N>;
+    fn add(self, rhs: &Dir<N>) -> Pos<N> {
+        self + &rhs.0
+    }
 }
 
-impl FromStr for Dir {
+// Letters moving back and forth along each axis, in the order they are written
+const AXES: [(usize, char, char); 3] = [(1, 'U', 'D'), (0, 'L', 'R'), (2, 'B', 'F')];
+
+// Unit step with at most one letter per axis, like "R", "UL" or "DRF"
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Dir<const N: usize>(Pos<N>);
+
+impl<const N: usize> FromStr for Dir<N> {
     type Err = &'static str;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let result = match s {
-            "R" => Dir::R,
-            "L" => Dir::L,
-            "D" => Dir::D,
-            "U" => Dir::U,
-            _ => Err("Invalid direction")?,
-        };
-        Ok(result)
+        let mut delta = Pos::ORIGIN;
+        for c in s.chars() {
+            let (axis, step) = AXES
+                .iter()
+                .find_map(|&(axis, back, forth)| match c {
+                    _ if c == back => Some((axis, -1)),
+                    _ if c == forth => Some((axis, 1)),
+                    _ => None,
+                })
+                .ok_or("Invalid direction")?;
+            if axis >= N {
+                return Err("Direction has more dimensions than the rope");
+            }
+            if delta.0[axis] != 0 {
+                return Err("Direction repeats an axis");
+            }
+            delta.0[axis] = step;
+        }
+        if delta == Pos::ORIGIN {
+            return Err("Invalid direction");
+        }
+        Ok(Self(delta))
     }
 }
 
-fn parse_input(input: &str) -> Vec<(Dir, u32)> {
+impl<const N: usize> Display for Dir<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for &(axis, back, forth) in AXES.iter().filter(|(axis, _, _)| *axis < N) {
+            match self.0 .0[axis] {
+                -1 => write!(f, "{back}")?,
+                1 => write!(f, "{forth}")?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct ParseError {
+    line: usize,
+    reason: &'static str,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.reason)
+    }
+}
+
+fn parse_input<const N: usize>(input: &str) -> Result<Vec<(Dir<N>, u32)>, ParseError> {
     input
         .lines()
-        .flat_map(|line| {
-            line.split_once(' ')
-                .and_then(|(c, n)| Some((c.parse::<Dir>().ok()?, n.parse::<u32>().ok()?)))
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let error = |reason| ParseError {
+                line: i + 1,
+                reason,
+            };
+            let (dir, n) = line
+                .trim()
+                .split_once(' ')
+                .ok_or(error("Expected a direction and a step count"))?;
+            let dir = dir.parse().map_err(error)?;
+            let n = n.trim().parse().map_err(|_| error("Invalid step count"))?;
+            Ok((dir, n))
         })
         .collect()
 }
 
-fn new_pos(head: &Pos, tail: &Pos, dir: &Dir) -> (Pos, Pos) {
-    let new_head = head + dir;
-    let new_tail = tail_pos_to_head(head, tail);
-    (new_head, new_tail)
+// Where a knot ends up after its leader has moved
+fn tail_pos_to_head<const N: usize>(head: &Pos<N>, tail: &Pos<N>) -> Pos<N> {
+    let delta = head - tail;
+    if delta.0.iter().all(|d| d.abs() <= 1) {
+        *tail
+    } else {
+        tail + &Pos(delta.0.map(|d| d.clamp(-1, 1)))
+    }
 }
 
-fn tail_pos_to_head(head: &Pos, tail: &Pos) -> Pos {
-    let Pos(dx, dy) = head - tail;
-    if dx.abs() + dy.abs() == 1 || dx.abs() == 1 && dy.abs() == 1 {
-        tail.clone()
-    } else {
-        tail + &Pos(dx.min(1).max(-1), dy.min(1).max(-1))
+// Knots from head to tail, each remembering every cell it has been in
+struct Rope<const N: usize> {
+    knots: Vec<Pos<N>>,
+    visited: Vec<HashSet<Pos<N>>>,
+}
+
+impl<const N: usize> Rope<N> {
+    fn new(knots: usize) -> Self {
+        assert!(knots > 0, "A rope needs at least one knot");
+        Self {
+            knots: vec![Pos::ORIGIN; knots],
+            visited: vec![HashSet::from([Pos::ORIGIN]); knots],
+        }
     }
+
+    // Moves the head one cell and lets the rest of the rope follow
+    fn step(&mut self, dir: &Dir<N>) {
+        self.knots[0] = &self.knots[0] + dir;
+        self.visited[0].insert(self.knots[0]);
+        for i in 1..self.knots.len() {
+            let knot = tail_pos_to_head(&self.knots[i - 1], &self.knots[i]);
+            if knot == self.knots[i] {
+                // Nothing further down the rope can move either
+                break;
+            }
+            self.visited[i].insert(knot);
+            self.knots[i] = knot;
+        }
+    }
+
+    fn apply(&mut self, moves: &[(Dir<N>, u32)]) {
+        for (dir, n) in moves {
+            for _ in 0..*n {
+                self.step(dir);
+            }
+        }
+    }
+
+    fn tail(&self) -> &Pos<N> {
+        self.knots.last().expect("A rope has at least one knot")
+    }
+
+    // Number of distinct cells each knot has visited, head first
+    fn visited_counts(&self) -> Vec<usize> {
+        self.visited.iter().map(HashSet::len).collect()
+    }
+}
+
+fn tail_visits<const N: usize>(moves: &[(Dir<N>, u32)], knots: usize) -> usize {
+    let mut rope = Rope::<N>::new(knots);
+    rope.apply(moves);
+    rope.visited_counts()[knots - 1]
 }
 
-fn part1(input: &str) -> usize {
-    let parsed = parse_input(input);
-    let mut tail_positions = HashSet::<Pos>::new();
-    let mut head = Pos(0, 0);
-    let mut tail = Pos(0, 0);
-    for (dir, n) in parsed.iter() {
-        for _ in 0..*n {
-            (head, tail) = new_pos(&head, &tail, dir);
-            tail_positions.insert(tail.clone());
+// Cells visited by every knot and where the tail ends up
+fn report<const N: usize>(moves: &[(Dir<N>, u32)], knots: usize) {
+    let mut rope = Rope::<N>::new(knots);
+    rope.apply(moves);
+    for (i, count) in rope.visited_counts().into_iter().enumerate() {
+        println!("Knot {i}: {count}");
+    }
+    println!("Tail ends at {:?}", rope.tail().0);
+}
+
+fn part1(moves: &[(Dir<2>, u32)]) -> usize {
+    tail_visits(moves, 2)
+}
+
+fn part2(moves: &[(Dir<2>, u32)]) -> usize {
+    tail_visits(moves, 10)
+}
+
+// Part of the grid shown on screen, scrolling to keep the head away from its edges
+#[derive(Clone, Debug, PartialEq)]
+struct Viewport {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl Viewport {
+    fn centered_on(pos: &Pos<2>, width: i32, height: i32) -> Self {
+        Self {
+            x: pos.0[0] - width / 2,
+            y: pos.0[1] - height / 2,
+            width,
+            height,
+        }
+    }
+
+    fn follow(&mut self, pos: &Pos<2>) {
+        fn scroll(start: &mut i32, len: i32, p: i32) {
+            let margin = (len / 4).min((len - 1) / 2);
+            if p < *start + margin {
+                *start = p - margin;
+            } else if p >= *start + len - margin {
+                *start = p - len + margin + 1;
+            }
         }
+        scroll(&mut self.x, self.width, pos.0[0]);
+        scroll(&mut self.y, self.height, pos.0[1]);
     }
-    tail_positions.len()
 }
 
-fn part2(input: &str) -> usize {
-    let parsed = parse_input(input);
-    let mut tail_positions = HashSet::<Pos>::new();
-    let mut rope: Vec<Pos> = vec![Pos(0, 0); 10];
+// 'H' for the head, then 1-9 and a-z for the knots behind it
+fn knot_char(i: usize) -> char {
+    match i {
+        0 => 'H',
+        1..=9 => (b'0' + i as u8) as char,
+        10..=35 => (b'a' + i as u8 - 10) as char,
+        _ => '+',
+    }
+}
+
+// Knots over the start 's' over the cells the tail has visited '#'
+fn render_frame(rope: &Rope<2>, view: &Viewport) -> String {
+    let mut knots = HashMap::new();
+    for (i, knot) in rope.knots.iter().enumerate().rev() {
+        knots.insert(knot, i);
+    }
+    let trail = rope.visited.last().expect("A rope has at least one knot");
+    (view.y..view.y + view.height)
+        .map(|y| {
+            (view.x..view.x + view.width)
+                .map(|x| {
+                    let pos = Pos([x, y]);
+                    match knots.get(&pos) {
+                        Some(&i) => knot_char(i),
+                        None if pos == Pos::ORIGIN => 's',
+                        None if trail.contains(&pos) => '#',
+                        None => '.',
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Frames of a rope following the moves, one per step of the head
+struct Animation<'a> {
+    rope: Rope<2>,
+    view: Viewport,
+    moves: &'a [(Dir<2>, u32)],
+    current: usize, // index into `moves`
+    taken: u32,     // steps taken of the current move
+    step: usize,
+    started: bool,
+}
 
-    for (dir, n) in parsed.iter() {
-        for _ in 0..*n {
-            let (head, _) = new_pos(&rope[0], &rope[1], dir);
-            rope[0] = head;
-            for i in 0..rope.len() - 1 {
-                let tail = tail_pos_to_head(&rope[i], &rope[i + 1]);
-                rope[i + 1] = tail;
+impl<'a> Animation<'a> {
+    fn new(moves: &'a [(Dir<2>, u32)], knots: usize, view: Viewport) -> Self {
+        Self {
+            rope: Rope::new(knots),
+            view,
+            moves,
+            current: 0,
+            taken: 0,
+            step: 0,
+            started: false,
+        }
+    }
+
+    fn frame(&self, label: &str) -> String {
+        format!(
+            "Step {} {}, tail visited {}\n{}",
+            self.step,
+            label,
+            self.rope.visited_counts().last().unwrap_or(&0),
+            render_frame(&self.rope, &self.view)
+        )
+    }
+}
+
+impl Iterator for Animation<'_> {
+    type Item = String;
+    fn next(&mut self) -> Option<String> {
+        if !self.started {
+            self.started = true;
+            return Some(self.frame("(start)"));
+        }
+        while self.moves.get(self.current)?.1 == self.taken {
+            self.current += 1;
+            self.taken = 0;
+        }
+        let (dir, n) = self.moves[self.current];
+        self.rope.step(&dir);
+        self.view.follow(&self.rope.knots[0]);
+        self.taken += 1;
+        self.step += 1;
+        let label = format!(
+            "(move {}/{}: {} {}, {}/{})",
+            self.current + 1,
+            self.moves.len(),
+            dir,
+            n,
+            self.taken,
+            n
+        );
+        Some(self.frame(&label))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Action {
+    Wait,
+    Advance,
+    Quit,
+}
+
+// Plays frames in the terminal, controlled by lines typed on stdin
+struct Player {
+    delay: Duration,
+    paused: bool,
+}
+
+impl Player {
+    const HELP: &'static str = "Enter: pause/resume, s: single step, +/-: speed, q: quit";
+
+    fn control(&mut self, command: &str) -> Action {
+        match command.trim() {
+            "" | "p" => {
+                self.paused = !self.paused;
+                if self.paused {
+                    Action::Wait
+                } else {
+                    Action::Advance
+                }
+            }
+            "s" => {
+                self.paused = true;
+                Action::Advance
+            }
+            "+" => {
+                self.delay = (self.delay / 2).max(Duration::from_millis(1));
+                Action::Wait
+            }
+            "-" => {
+                self.delay = (self.delay * 2).min(Duration::from_secs(5));
+                Action::Wait
+            }
+            "q" => Action::Quit,
+            _ => Action::Wait,
+        }
+    }
+
+    fn play(&mut self, frames: impl Iterator<Item = String>) {
+        let (sender, commands) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut stdout = io::stdout();
+        let mut input_closed = false;
+        for frame in frames {
+            let state = if self.paused { "paused" } else { "playing" };
+            write!(
+                stdout,
+                "\x1b[2J\x1b[H{frame}\n[{state}, {}ms per frame] {}\n",
+                self.delay.as_millis(),
+                Self::HELP
+            )
+            .and_then(|_| stdout.flush())
+            .expect("Could not write to terminal");
+            loop {
+                if input_closed {
+                    // Nobody can resume a paused animation any more
+                    self.paused = false;
+                    thread::sleep(self.delay);
+                    break;
+                }
+                let received = match self.paused {
+                    true => commands.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                    false => commands.recv_timeout(self.delay),
+                };
+                match received {
+                    Ok(command) => match self.control(&command) {
+                        Action::Wait => {}
+                        Action::Advance => break,
+                        Action::Quit => return,
+                    },
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => input_closed = true,
+                }
             }
-            tail_positions.insert(rope[9].clone());
         }
     }
-    tail_positions.len()
 }
 
 fn main() {
     let input = fs::read_to_string("inputs/day09.txt").expect("Could not read input file");
-    let part1 = part1(&input);
-    let part2 = part2(&input);
-    println!("Part 1: {part1}");
-    println!("Part 2: {part2}");
+    let mut knots: Option<usize> = None;
+    let mut animate = false;
+    let mut player = Player {
+        delay: Duration::from_millis(100),
+        paused: false,
+    };
+    let mut dump: Option<String> = None;
+    let mut three_d = false;
+    let (mut width, mut height) = (60, 20);
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut number = || args.next().and_then(|n| n.parse::<u64>().ok());
+        match arg.as_str() {
+            "--knots" => knots = number().map(|n| n as usize).filter(|&n| n > 0),
+            "--animate" => animate = true,
+            "--delay" => player.delay = Duration::from_millis(number().unwrap_or(100)),
+            "--step" => player.paused = true,
+            "--width" => width = number().unwrap_or(60) as i32,
+            "--height" => height = number().unwrap_or(20) as i32,
+            "--dump" => dump = args.next(),
+            "--3d" => three_d = true,
+            _ => {}
+        }
+    }
+
+    let fail = |e: &dyn Display| -> ! {
+        eprintln!("{e}");
+        process::exit(1)
+    };
+    if three_d {
+        if animate || dump.is_some() {
+            fail(&"Only flat ropes can be animated, --3d cannot be combined with --animate or --dump");
+        }
+        let moves = parse_input::<3>(&input).unwrap_or_else(|e| fail(&e));
+        match knots {
+            Some(knots) => report(&moves, knots),
+            None => {
+                println!("Part 1 (3D): {}", tail_visits(&moves, 2));
+                println!("Part 2 (3D): {}", tail_visits(&moves, 10));
+            }
+        }
+        return;
+    }
+
+    let moves = parse_input::<2>(&input).unwrap_or_else(|e| fail(&e));
+    if animate || dump.is_some() {
+        let view = Viewport::centered_on(&Pos::ORIGIN, width.max(1), height.max(1));
+        let frames = Animation::new(&moves, knots.unwrap_or(10), view);
+        match dump {
+            Some(path) => {
+                let mut file = BufWriter::new(File::create(path).expect("Could not create file"));
+                for frame in frames {
+                    writeln!(file, "{frame}\n").expect("Could not write frame");
+                }
+            }
+            None => player.play(frames),
+        }
+        return;
+    }
+    match knots {
+        Some(knots) => report(&moves, knots),
+        None => {
+            let part1 = part1(&moves);
+            let part2 = part2(&moves);
+            println!("Part 1: {part1}");
+            println!("Part 2: {part2}");
+        }
+    }
 }
 
 #[cfg(test)]
@@ -130,12 +510,262 @@ D 1
 L 5
 R 2";
 
+    fn moves<const N: usize>(input: &str) -> Vec<(Dir<N>, u32)> {
+        parse_input(input).unwrap()
+    }
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1(TEST_INPUT), 13);
+        assert_eq!(part1(&moves(TEST_INPUT)), 13);
     }
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST_INPUT), 1);
+        assert_eq!(part2(&moves(TEST_INPUT)), 1);
+    }
+    const LARGER_INPUT: &str = "\
+R 5
+U 8
+L 8
+D 3
+R 17
+D 10
+L 25
+U 20";
+
+    #[test]
+    fn test_larger_example() {
+        assert_eq!(part2(&moves(LARGER_INPUT)), 36);
+        let mut rope = Rope::<2>::new(10);
+        rope.apply(&parse_input(LARGER_INPUT).unwrap());
+        assert_eq!(rope.knots[0], Pos([-11, -15]));
+        assert_eq!(
+            rope.visited_counts()[1],
+            tail_visits(&moves::<2>(LARGER_INPUT), 2)
+        );
+    }
+    #[test]
+    fn test_per_knot_counts() {
+        let mut rope = Rope::<2>::new(3);
+        rope.apply(&parse_input("R 4").unwrap());
+        assert_eq!(rope.visited_counts(), vec![5, 4, 3]);
+        assert_eq!(rope.knots, vec![Pos([4, 0]), Pos([3, 0]), Pos([2, 0])]);
+        let mut single = Rope::<2>::new(1);
+        single.apply(&parse_input("R 2\nUL 2").unwrap());
+        assert_eq!(single.tail(), &Pos([0, -2]));
+        assert_eq!(single.visited_counts(), vec![5]);
+    }
+    #[test]
+    fn test_diagonal_moves() {
+        let mut rope = Rope::<2>::new(2);
+        rope.apply(&parse_input("DR 3").unwrap());
+        assert_eq!(rope.knots, vec![Pos([3, 3]), Pos([2, 2])]);
+        assert_eq!(rope.visited_counts(), vec![4, 3]);
+        rope.step(&"UR".parse().unwrap());
+        assert_eq!(rope.knots, vec![Pos([4, 2]), Pos([3, 2])]);
+        // A diagonal step back onto the tail leaves it in place
+        rope.step(&"DL".parse().unwrap());
+        assert_eq!(rope.knots, vec![Pos([3, 3]), Pos([3, 2])]);
+    }
+    // Every direction, with runs long enough to drag a whole rope of 10 around corners
+    const WINDING_INPUT: &str = "R 5\nUR 4\nL 8\nDL 3\nD 6\nDR 7\nU 9\nUL 5";
+
+    #[test]
+    fn test_knots_stay_together() {
+        for knots in [1, 2, 5, 10, 12] {
+            let mut rope = Rope::<2>::new(knots);
+            for (dir, n) in moves::<2>(WINDING_INPUT) {
+                for _ in 0..n {
+                    rope.step(&dir);
+                    for pair in rope.knots.windows(2) {
+                        let Pos([dx, dy]) = &pair[0] - &pair[1];
+                        assert!(dx.abs() <= 1 && dy.abs() <= 1, "{:?}", rope.knots);
+                    }
+                }
+            }
+            for (knot, visited) in rope.knots.iter().zip(&rope.visited) {
+                assert!(visited.contains(knot) && visited.contains(&Pos([0, 0])));
+            }
+        }
+    }
+    #[test]
+    fn test_rope_pulls_straight() {
+        let mut rope = Rope::<2>::new(10);
+        rope.apply(&moves("R 12"));
+        assert_eq!(
+            rope.knots,
+            (0..10).map(|i| Pos([12 - i, 0])).collect::<Vec<_>>()
+        );
+        // Every knot follows the one ahead of it diagonally, so the rope shifts as a whole
+        rope.apply(&moves("UR 12"));
+        assert_eq!(
+            rope.knots,
+            (0..10).map(|i| Pos([24 - i, -12])).collect::<Vec<_>>()
+        );
+        assert_eq!(rope.visited_counts()[9], 4 + 12);
+    }
+    #[test]
+    fn test_render_frame() {
+        let moves = parse_input(TEST_INPUT).unwrap();
+        let view = Viewport {
+            x: 0,
+            y: -4,
+            width: 6,
+            height: 5,
+        };
+        let mut frames = Animation::new(&moves, 10, view.clone());
+        assert_eq!(
+            frames.next().unwrap(),
+            "Step 0 (start), tail visited 1\n......\n......\n......\n......\nH....."
+        );
+        let mut rope = Rope::<2>::new(10);
+        rope.apply(&parse_input("R 4").unwrap());
+        assert_eq!(
+            render_frame(&rope, &view),
+            "......\n......\n......\n......\n4321H."
+        );
+        let mut rope = Rope::<2>::new(2);
+        rope.apply(&moves);
+        assert_eq!(
+            render_frame(&rope, &view),
+            "..##..\n...##.\n.1H##.\n....#.\ns###.."
+        );
+    }
+    #[test]
+    fn test_frames_cover_every_step() {
+        let moves = parse_input(TEST_INPUT).unwrap();
+        let view = Viewport::centered_on(&Pos([0, 0]), 3, 3);
+        let frames = Animation::new(&moves, 2, view).collect::<Vec<String>>();
+        assert_eq!(frames.len(), 1 + 24);
+        assert!(frames[24].starts_with("Step 24 (move 8/8: R 2, 2/2), tail visited 13\n"));
+        assert_eq!(
+            Animation::new(&[], 2, Viewport::centered_on(&Pos([0, 0]), 1, 1)).count(),
+            1
+        );
+    }
+    #[test]
+    fn test_viewport_scrolls() {
+        let mut view = Viewport::centered_on(&Pos([0, 0]), 8, 4);
+        assert_eq!((view.x, view.y), (-4, -2));
+        view.follow(&Pos([1, 0]));
+        assert_eq!((view.x, view.y), (-4, -2));
+        // Stays two columns and one row away from the edges
+        view.follow(&Pos([2, 1]));
+        assert_eq!((view.x, view.y), (-3, -1));
+        view.follow(&Pos([-10, -10]));
+        assert_eq!((view.x, view.y), (-12, -11));
+        let mut tiny = Viewport::centered_on(&Pos([0, 0]), 1, 1);
+        tiny.follow(&Pos([5, -5]));
+        assert_eq!((tiny.x, tiny.y), (5, -5));
+    }
+    #[test]
+    fn test_player_controls() {
+        let mut player = Player {
+            delay: Duration::from_millis(100),
+            paused: false,
+        };
+        assert_eq!(player.control("+"), Action::Wait);
+        assert_eq!(player.delay, Duration::from_millis(50));
+        assert_eq!(player.control(" - "), Action::Wait);
+        assert_eq!(player.delay, Duration::from_millis(100));
+        assert_eq!(player.control(""), Action::Wait);
+        assert!(player.paused);
+        assert_eq!(player.control("s"), Action::Advance);
+        assert!(player.paused);
+        assert_eq!(player.control("p"), Action::Advance);
+        assert!(!player.paused);
+        assert_eq!(player.control("x"), Action::Wait);
+        assert_eq!(player.control("q"), Action::Quit);
+    }
+    #[test]
+    fn test_directions() {
+        for name in ["R", "UL", "DR", "F", "UB", "DLF"] {
+            assert_eq!(name.parse::<Dir<3>>().unwrap().to_string(), name);
+        }
+        assert_eq!("RU".parse::<Dir<2>>().unwrap().to_string(), "UR");
+        assert_eq!("F".parse::<Dir<3>>(), Ok(Dir(Pos([0, 0, 1]))));
+        assert_eq!(
+            "F".parse::<Dir<2>>(),
+            Err("Direction has more dimensions than the rope")
+        );
+        assert_eq!("LR".parse::<Dir<2>>(), Err("Direction repeats an axis"));
+        assert_eq!("X".parse::<Dir<2>>(), Err("Invalid direction"));
+        assert_eq!("".parse::<Dir<2>>(), Err("Invalid direction"));
+    }
+    #[test]
+    fn test_parse_errors() {
+        let error = |line, reason| ParseError { line, reason };
+        assert_eq!(
+            parse_input::<2>("R 4\nF 3").unwrap_err(),
+            error(2, "Direction has more dimensions than the rope")
+        );
+        assert_eq!(
+            parse_input::<3>("R 4\n\nUD 1").unwrap_err(),
+            error(3, "Direction repeats an axis")
+        );
+        assert_eq!(
+            parse_input::<2>("R").unwrap_err(),
+            error(1, "Expected a direction and a step count")
+        );
+        assert_eq!(
+            parse_input::<2>("R x").unwrap_err(),
+            error(1, "Invalid step count")
+        );
+        assert_eq!(parse_input::<3>("F 3\n").map(|m| m.len()), Ok(1));
+        assert_eq!(
+            error(2, "Invalid step count").to_string(),
+            "Line 2: Invalid step count"
+        );
+    }
+    #[test]
+    fn test_flat_ropes_in_3d() {
+        // A rope that never leaves the z = 0 plane behaves exactly like the 2D one
+        assert_eq!(tail_visits(&moves::<3>(TEST_INPUT), 2), 13);
+        assert_eq!(tail_visits(&moves::<3>(TEST_INPUT), 10), 1);
+        assert_eq!(tail_visits(&moves::<3>(LARGER_INPUT), 10), 36);
+        for input in [WINDING_INPUT, LARGER_INPUT] {
+            let flat = moves::<2>(input);
+            let lifted = flat
+                .iter()
+                .map(|(Dir(Pos([x, y])), n)| (Dir(Pos([*x, *y, 0])), *n))
+                .collect::<Vec<(Dir<3>, u32)>>();
+            let (mut rope2, mut rope3) = (Rope::<2>::new(10), Rope::<3>::new(10));
+            rope2.apply(&flat);
+            rope3.apply(&lifted);
+            assert_eq!(rope2.visited_counts(), rope3.visited_counts());
+            let Pos([x, y]) = *rope2.tail();
+            assert_eq!(rope3.tail(), &Pos([x, y, 0]));
+        }
+    }
+    #[test]
+    fn test_rope_in_3d() {
+        let mut rope = Rope::<3>::new(3);
+        rope.apply(&parse_input("F 3\nU 2").unwrap());
+        assert_eq!(
+            rope.knots,
+            vec![Pos([0, -2, 3]), Pos([0, -1, 3]), Pos([0, -1, 2])]
+        );
+        assert_eq!(rope.visited_counts(), vec![6, 4, 3]);
+        // Straight and space-diagonal runs along every axis
+        let winding = moves::<3>("F 4\nU 3\nR 5\nB 6\nURF 3\nD 2\nL 7\nDLB 4");
+        for knots in [2, 10] {
+            let mut rope = Rope::<3>::new(knots);
+            for (dir, n) in &winding {
+                for _ in 0..*n {
+                    rope.step(dir);
+                    for pair in rope.knots.windows(2) {
+                        let delta = &pair[0] - &pair[1];
+                        assert!(delta.0.iter().all(|d| d.abs() <= 1), "{:?}", rope.knots);
+                    }
+                }
+            }
+        }
+        // A straight rope moved diagonally shifts as a whole, as in the plane
+        let mut rope = Rope::<3>::new(10);
+        rope.apply(&moves("R 12\nURF 12"));
+        assert_eq!(
+            rope.knots,
+            (0..10).map(|i| Pos([24 - i, -12, 12])).collect::<Vec<_>>()
+        );
+        assert_eq!(rope.visited_counts()[9], 4 + 12);
     }
 }
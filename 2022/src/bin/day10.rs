@@ -1,100 +1,657 @@
-use std::{fs, str::FromStr};
+use aoc::ocr;
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::Display,
+    fs,
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+// The register file, by name and value at start. Only the extended table uses y.
+const REGISTERS: [(char, i32); 2] = [('x', 1), ('y', 0)];
+
+// One entry of an instruction table: how the opcode is written, how many
+// integer operands it takes, how many cycles it occupies and what it does
+// once its last cycle completes
+#[derive(Clone, Copy, Debug)]
+struct Opcode {
+    name: &'static str,
+    operands: usize,
+    cycles: usize,
+    execute: fn(&mut Cpu, &[i32]),
+}
+
+const BASE: [Opcode; 2] = [
+    Opcode {
+        name: "noop",
+        operands: 0,
+        cycles: 1,
+        execute: |_, _| {},
+    },
+    Opcode {
+        name: "addx",
+        operands: 1,
+        cycles: 2,
+        execute: |cpu, args| *cpu.register_mut('x') += args[0],
+    },
+];
+
+// The base set plus a second register and relative jumps
+const EXTENDED: [Opcode; 5] = [
+    BASE[0],
+    BASE[1],
+    Opcode {
+        name: "addy",
+        operands: 1,
+        cycles: 2,
+        execute: |cpu, args| *cpu.register_mut('y') += args[0],
+    },
+    Opcode {
+        name: "jmp",
+        operands: 1,
+        cycles: 1,
+        execute: |cpu, args| cpu.jump(args[0]),
+    },
+    Opcode {
+        name: "jnz",
+        operands: 1,
+        cycles: 1,
+        execute: |cpu, args| {
+            if cpu.x() != 0 {
+                cpu.jump(args[0])
+            }
+        },
+    },
+];
 
 #[derive(Debug)]
-enum Instr {
-    Noop,
-    Addx(i32),
+struct Instr<'a> {
+    opcode: &'a Opcode,
+    args: Vec<i32>,
 }
 
-impl FromStr for Instr {
-    type Err = &'static str;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut t = s.split(' ').take(2);
-        match (t.next(), t.next()) {
-            (Some("addx"), Some(v)) => Ok(Instr::Addx(v.parse().map_err(|_| "Not a number")?)),
-            (Some("noop"), _) => Ok(Instr::Noop),
-            _ => Err("Invalid instruction"),
+impl Display for Instr<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.opcode.name)?;
+        for arg in &self.args {
+            write!(f, " {arg}")?;
         }
+        Ok(())
     }
 }
 
-impl Instr {
-    fn value(&self) -> (i32, i32) {
-        match self {
-            Instr::Noop => (1, 0),
-            Instr::Addx(v) => (2, *v),
-        }
+fn parse_instr<'a>(line: &str, isa: &'a [Opcode]) -> Result<Instr<'a>, &'static str> {
+    let mut tokens = line.split_whitespace();
+    let name = tokens.next().ok_or("Empty instruction")?;
+    let opcode = isa
+        .iter()
+        .find(|op| op.name == name)
+        .ok_or("Invalid instruction")?;
+    let args = tokens
+        .map(|t| t.parse().map_err(|_| "Not a number"))
+        .collect::<Result<Vec<i32>, _>>()?;
+    if args.len() != opcode.operands {
+        return Err("Wrong number of operands");
     }
+    Ok(Instr { opcode, args })
 }
 
-fn parse_input(input: &str) -> Vec<Instr> {
+fn parse_program<'a>(input: &str, isa: &'a [Opcode]) -> Vec<Instr<'a>> {
     input
         .lines()
-        .map(Instr::from_str)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| parse_instr(line, isa))
         .collect::<Result<Vec<Instr>, _>>()
         .expect("Invalid input")
 }
 
-fn part1(input: &str) -> i32 {
-    let instructions = parse_input(input);
-
-    let mut cycle = 0i32;
-    let mut reg = 1i32;
-    let mut signal = 0i32;
-
-    for instr in instructions.iter() {
-        let (cycles, to_add) = instr.value();
-        for _ in 0..cycles {
-            cycle += 1;
-            if cycle == 20 || (cycle - 20) % 40 == 0 {
-                signal += cycle * reg;
-            }
+// Part 1 samples the signal during cycle 20 and every 40 cycles after it
+const FIRST_SAMPLE: usize = 20;
+const SAMPLE_INTERVAL: usize = 40;
+
+// Jumps let programs loop forever, so runs stop after this many cycles by default
+const MAX_CYCLES: usize = 1_000_000;
+
+#[derive(Debug)]
+struct Cpu<'a> {
+    program: &'a [Instr<'a>],
+    registers: BTreeMap<char, i32>,
+    pc: usize,
+    cycle: usize, // the cycle in progress while hooks run, else the last one completed
+    busy: usize,  // cycles already spent on the instruction at pc
+    max_cycles: usize,
+}
+
+impl<'a> Cpu<'a> {
+    fn new(program: &'a [Instr<'a>]) -> Self {
+        Self {
+            program,
+            registers: BTreeMap::from(REGISTERS),
+            pc: 0,
+            cycle: 0,
+            busy: 0,
+            max_cycles: MAX_CYCLES,
+        }
+    }
+
+    fn register(&self, name: char) -> i32 {
+        self.registers[&name]
+    }
+
+    fn register_mut(&mut self, name: char) -> &mut i32 {
+        self.registers.get_mut(&name).expect("No such register")
+    }
+
+    fn x(&self) -> i32 {
+        self.register('x')
+    }
+
+    fn signal_strength(&self) -> i32 {
+        self.cycle as i32 * self.x()
+    }
+
+    // The signal strength, if the cycle in progress is one that part 1 samples
+    fn sampled_signal(&self) -> Option<i32> {
+        let sampled = self.cycle >= FIRST_SAMPLE
+            && (self.cycle - FIRST_SAMPLE).is_multiple_of(SAMPLE_INTERVAL);
+        sampled.then(|| self.signal_strength())
+    }
+
+    fn halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+
+    // Still running, but not allowed any more cycles
+    fn at_limit(&self) -> bool {
+        !self.halted() && self.cycle >= self.max_cycles
+    }
+
+    // Moves relative to the instruction being executed; jumping outside the
+    // program halts it
+    fn jump(&mut self, offset: i32) {
+        self.pc = (self.pc - 1)
+            .checked_add_signed(offset as isize)
+            .unwrap_or(usize::MAX);
+    }
+
+    // Runs one clock cycle, calling `hook` while it is in progress, before the
+    // effect of an instruction finishing on it. Returns false once halted or
+    // out of cycles.
+    fn tick(&mut self, mut hook: impl FnMut(&Self)) -> bool {
+        if self.halted() || self.at_limit() {
+            return false;
+        }
+        let instr = &self.program[self.pc];
+        self.cycle += 1;
+        hook(self);
+        self.busy += 1;
+        if self.busy >= instr.opcode.cycles {
+            self.busy = 0;
+            self.pc += 1;
+            (instr.opcode.execute)(self, &instr.args);
         }
-        reg += to_add;
+        true
+    }
+
+    fn run(&mut self, mut hook: impl FnMut(&Self)) {
+        while self.tick(&mut hook) {}
     }
+}
+
+const CRT_WIDTH: usize = 40;
+
+// Pixels drawn so far, one per cycle, lit when the sprite at X covers the column
+#[derive(Debug, Default)]
+struct Crt {
+    pixels: Vec<bool>,
+}
+
+impl Crt {
+    fn draw(&mut self, cpu: &Cpu) {
+        let column = ((cpu.cycle - 1) % CRT_WIDTH) as i32;
+        self.pixels.push((cpu.x() - column).abs() <= 1);
+    }
+
+    fn render(&self) -> String {
+        self.pixels
+            .chunks(CRT_WIDTH)
+            .map(|row| row.iter().map(|&lit| if lit { '#' } else { '.' }).collect())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+fn signal_sum(program: &[Instr]) -> i32 {
+    let mut signal = 0;
+    Cpu::new(program).run(|cpu| signal += cpu.sampled_signal().unwrap_or(0));
     signal
 }
 
+fn draw(program: &[Instr]) -> Crt {
+    let mut crt = Crt::default();
+    Cpu::new(program).run(|cpu| crt.draw(cpu));
+    crt
+}
+
+fn part1(input: &str) -> i32 {
+    signal_sum(&parse_program(input, &BASE))
+}
+
 fn part2(input: &str) -> String {
-    let instructions = parse_input(input);
-
-    let mut cycle = 0i32;
-    let mut reg = 1i32;
-    let mut pixels: Vec<char> = vec![];
-
-    for instr in instructions.iter() {
-        let (cycles, to_add) = instr.value();
-        for _ in 0..cycles {
-            if (reg - cycle % 40).abs() <= 1 {
-                pixels.push('#');
-            } else {
-                pixels.push('.');
+    ocr::read(&draw(&parse_program(input, &BASE)).render())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Breakpoint {
+    Cycle(usize),
+    Instr(usize),
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cycle(n) => write!(f, "cycle {n}"),
+            Self::Instr(i) => write!(f, "instruction {i}"),
+        }
+    }
+}
+
+// Values the debugger can print or watch, taken during the cycle about to run
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Expr {
+    Register(char),
+    Cycle,
+    Pc,
+    Signal,
+    Sum, // of the signal strengths sampled so far, as in part 1
+}
+
+impl FromStr for Expr {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(&(name, _)) = REGISTERS.iter().find(|&&(name, _)| s.chars().eq([name])) {
+            return Ok(Self::Register(name));
+        }
+        match s {
+            "cycle" => Ok(Self::Cycle),
+            "pc" => Ok(Self::Pc),
+            "signal" => Ok(Self::Signal),
+            "sum" => Ok(Self::Sum),
+            _ => Err("Unknown expression"),
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Register(name) => return write!(f, "{name}"),
+            Self::Cycle => "cycle",
+            Self::Pc => "pc",
+            Self::Signal => "signal",
+            Self::Sum => "sum",
+        };
+        write!(f, "{name}")
+    }
+}
+
+// Steps a program between cycles, drawing the CRT and summing signal
+// strengths as it goes
+struct Debugger<'a> {
+    cpu: Cpu<'a>,
+    crt: Crt,
+    sum: i32,
+    breakpoints: Vec<Breakpoint>,
+    watches: Vec<Expr>,
+}
+
+impl<'a> Debugger<'a> {
+    fn new(program: &'a [Instr<'a>]) -> Self {
+        Self {
+            cpu: Cpu::new(program),
+            crt: Crt::default(),
+            sum: 0,
+            breakpoints: vec![],
+            watches: vec![],
+        }
+    }
+
+    fn eval(&self, expr: Expr) -> i32 {
+        let cycle = self.cpu.cycle as i32 + 1;
+        match expr {
+            Expr::Register(name) => self.cpu.register(name),
+            Expr::Cycle => cycle,
+            Expr::Pc => self.cpu.pc as i32,
+            Expr::Signal => cycle * self.cpu.x(),
+            Expr::Sum => self.sum,
+        }
+    }
+
+    // Number of the first breakpoint on the cycle about to run
+    fn hit(&self) -> Option<usize> {
+        if self.cpu.halted() {
+            return None;
+        }
+        let position = self.breakpoints.iter().position(|&b| match b {
+            Breakpoint::Cycle(n) => self.cpu.cycle + 1 == n,
+            Breakpoint::Instr(i) => self.cpu.pc == i && self.cpu.busy == 0,
+        });
+        position.map(|i| i + 1)
+    }
+
+    fn tick(&mut self) -> bool {
+        self.cpu.tick(|cpu| {
+            self.crt.draw(cpu);
+            self.sum += cpu.sampled_signal().unwrap_or(0);
+        })
+    }
+
+    // Runs at least one cycle, then on until `stop` holds or the program halts
+    fn resume(&mut self, mut stop: impl FnMut(&Self) -> bool) -> String {
+        while self.tick() && !stop(self) {}
+        self.report()
+    }
+
+    // Where the program stopped, the watched values and the CRT so far
+    fn report(&self) -> String {
+        let cpu = &self.cpu;
+        let mut lines = vec![match self.hit() {
+            _ if cpu.halted() => format!("Halted after {} cycles, X = {}", cpu.cycle, cpu.x()),
+            _ if cpu.at_limit() => {
+                format!("Cycle limit of {} reached, X = {}", cpu.max_cycles, cpu.x())
             }
-            cycle += 1;
+            hit => {
+                let instr = &cpu.program[cpu.pc];
+                format!(
+                    "{}Cycle {}: {} at {} ({}/{}), X = {}",
+                    hit.map_or(String::new(), |n| format!("Breakpoint {n}, ")),
+                    cpu.cycle + 1,
+                    instr,
+                    cpu.pc,
+                    cpu.busy + 1,
+                    instr.opcode.cycles,
+                    cpu.x()
+                )
+            }
+        }];
+        lines.extend(
+            self.watches
+                .iter()
+                .map(|&e| format!("{e} = {}", self.eval(e))),
+        );
+        if !self.crt.pixels.is_empty() {
+            lines.push(self.crt.render());
+        }
+        lines.join("\n")
+    }
+
+    fn command(&mut self, line: &str) -> Result<String, &'static str> {
+        let mut args = line.split_whitespace();
+        let Some(command) = args.next() else {
+            return Ok(String::new());
+        };
+        let number = |arg: Option<&str>, default: Option<usize>| match arg {
+            Some(n) => n.parse().map_err(|_| "Not a number"),
+            None => default.ok_or("Expected a number"),
+        };
+        match command {
+            "break" | "b" => {
+                let breakpoint = match args.next() {
+                    Some("cycle") => Breakpoint::Cycle(number(args.next(), None)?),
+                    Some("instr") => Breakpoint::Instr(number(args.next(), None)?),
+                    _ => return Err("Expected cycle or instr after break"),
+                };
+                self.breakpoints.push(breakpoint);
+                Ok(format!(
+                    "Breakpoint {} at {breakpoint}",
+                    self.breakpoints.len()
+                ))
+            }
+            "delete" | "d" => match number(args.next(), Some(0))? {
+                0 => {
+                    self.breakpoints.clear();
+                    Ok("Deleted all breakpoints".to_string())
+                }
+                n if n <= self.breakpoints.len() => {
+                    self.breakpoints.remove(n - 1);
+                    Ok(format!("Deleted breakpoint {n}"))
+                }
+                _ => Err("No such breakpoint"),
+            },
+            "watch" | "w" => {
+                let expr = args.next().ok_or("Expected an expression")?.parse()?;
+                self.watches.push(expr);
+                Ok(format!("{expr} = {}", self.eval(expr)))
+            }
+            "print" | "p" => {
+                let expr: Expr = args.next().ok_or("Expected an expression")?.parse()?;
+                Ok(format!("{expr} = {}", self.eval(expr)))
+            }
+            "step" | "s" => {
+                let end = self.cpu.cycle.saturating_add(number(args.next(), Some(1))?);
+                Ok(self.resume(|d| d.cpu.cycle >= end || d.hit().is_some()))
+            }
+            "next" | "n" => Ok(self.resume(|d| d.cpu.busy == 0 || d.hit().is_some())),
+            "continue" | "c" => Ok(self.resume(|d| d.hit().is_some())),
+            "info" | "i" => {
+                let breakpoints = self
+                    .breakpoints
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| format!("Breakpoint {}: {b}", i + 1));
+                let watches = self.watches.iter().map(|e| format!("Watching {e}"));
+                Ok(breakpoints
+                    .chain(watches)
+                    .collect::<Vec<String>>()
+                    .join("\n"))
+            }
+            "limit" => {
+                self.cpu.max_cycles = number(args.next(), None)?;
+                Ok(format!("Cycle limit set to {}", self.cpu.max_cycles))
+            }
+            "crt" => Ok(self.crt.render()),
+            "where" => Ok(self.report()),
+            _ => Err("Unknown command"),
         }
-        reg += to_add;
     }
-    let result = pixels
-        .chunks(40)
-        .map(|chars| chars.iter().collect())
-        .collect::<Vec<String>>()
-        .join("\n");
-    result
 }
 
 fn main() {
     let input = fs::read_to_string("inputs/day10.txt").expect("Could not read input file");
+    if env::args().any(|a| a == "--debug") {
+        let extended = env::args().any(|a| a == "--extended");
+        let program = parse_program(&input, if extended { &EXTENDED } else { &BASE });
+        let mut debugger = Debugger::new(&program);
+        println!("{}", debugger.report());
+        print!("> ");
+        io::stdout().flush().expect("Could not write output");
+        for line in io::stdin().lock().lines() {
+            let line = line.expect("Could not read command");
+            if matches!(line.trim(), "quit" | "q") {
+                break;
+            }
+            match debugger.command(&line) {
+                Ok(output) => println!("{output}"),
+                Err(e) => println!("{e}"),
+            }
+            print!("> ");
+            io::stdout().flush().expect("Could not write output");
+        }
+        return;
+    }
+    if env::args().any(|a| a == "--extended") {
+        let program = parse_program(&input, &EXTENDED);
+        let mut cpu = Cpu::new(&program);
+        cpu.run(|_| {});
+        match cpu.at_limit() {
+            true => println!("Cycle limit reached with {:?}", cpu.registers),
+            false => println!("Halted after {} cycles with {:?}", cpu.cycle, cpu.registers),
+        }
+        println!("Part 1: {}", signal_sum(&program));
+        println!("Part 2: {}", ocr::read(&draw(&program).render()));
+        return;
+    }
     let part1 = part1(&input);
     let part2 = part2(&input);
     println!("Part 1: {part1}");
-    println!("Part 2:\n{part2}");
+    println!("Part 2: {part2}");
+    if part2.contains('?') {
+        println!("{}", draw(&parse_program(&input, &BASE)).render());
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const TEST_INPUT: &str = include_str!("../../inputs/day10_test.txt");
+    const TEST_INPUT: &str = "\
+addx 15
+addx -11
+addx 6
+addx -3
+addx 5
+addx -1
+addx -8
+addx 13
+addx 4
+noop
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx -35
+addx 1
+addx 24
+addx -19
+addx 1
+addx 16
+addx -11
+noop
+noop
+addx 21
+addx -15
+noop
+noop
+addx -3
+addx 9
+addx 1
+addx -3
+addx 8
+addx 1
+addx 5
+noop
+noop
+noop
+noop
+noop
+addx -36
+noop
+addx 1
+addx 7
+noop
+noop
+noop
+addx 2
+addx 6
+noop
+noop
+noop
+noop
+noop
+addx 1
+noop
+noop
+addx 7
+addx 1
+noop
+addx -13
+addx 13
+addx 7
+noop
+addx 1
+addx -33
+noop
+noop
+noop
+addx 2
+noop
+noop
+noop
+addx 8
+noop
+addx -1
+addx 2
+addx 1
+noop
+addx 17
+addx -9
+addx 1
+addx 1
+addx -3
+addx 11
+noop
+noop
+addx 1
+noop
+addx 1
+noop
+noop
+addx -13
+addx -19
+addx 1
+addx 3
+addx 26
+addx -30
+addx 12
+addx -1
+addx 3
+addx 1
+noop
+noop
+noop
+addx -9
+addx 18
+addx 1
+addx 2
+noop
+noop
+addx 9
+noop
+noop
+noop
+addx -1
+addx 2
+addx -37
+addx 1
+addx 3
+noop
+addx 15
+addx -21
+addx 22
+addx -6
+addx 1
+noop
+addx 2
+addx 1
+noop
+addx -10
+noop
+noop
+addx 20
+addx 1
+addx 2
+addx 2
+addx -6
+addx -11
+noop
+noop
+noop";
 
     #[test]
     fn test_part1() {
@@ -109,6 +666,278 @@ mod tests {
 #####.....#####.....#####.....#####.....
 ######......######......######......####
 #######.......#######.......#######.....";
-        assert_eq!(part2(TEST_INPUT), expected);
+        assert_eq!(draw(&parse_program(TEST_INPUT, &BASE)).render(), expected);
+        // The example draws stripes rather than letters
+        assert_eq!(part2(TEST_INPUT), "????????");
+    }
+    // Draws `text` in `font`, with blank columns between letters as on the CRT
+    fn spell(font: &ocr::Font, text: &str) -> String {
+        let glyph = |c| font.glyphs.iter().find(|&&(l, _)| l == c).unwrap().1;
+        let gap = ".".repeat(font.spacing);
+        (0..font.height)
+            .map(|r| {
+                text.chars()
+                    .map(|c| match c {
+                        ' ' => ".".repeat(font.width),
+                        c => glyph(c)[r].to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(&gap)
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_read_letters() {
+        for font in [&ocr::SMALL, &ocr::LARGE] {
+            let text = font.glyphs.iter().map(|&(l, _)| l).collect::<String>();
+            assert_eq!(font.read(&spell(font, &text)), text);
+            assert_eq!(ocr::read(&spell(font, "HE LL")), "HE LL");
+        }
+        assert_eq!(ocr::read(&spell(&ocr::SMALL, "HILLY")), "HILLY");
+        assert_eq!(ocr::read(&spell(&ocr::SMALL, "FIZZY")), "FIZZY");
+        // A Z missing pixels is unknown, and trailing blank cells are dropped
+        let drawing = spell(&ocr::SMALL, "EZ  ").replace("####.####", "####.###.");
+        assert_eq!(ocr::read(&drawing), "E?");
+        // Two lines of text
+        let two_lines = spell(&ocr::SMALL, "AB") + "\n" + &spell(&ocr::SMALL, "CE");
+        assert_eq!(ocr::SMALL.read(&two_lines), "AB\nCE");
+        assert_eq!(ocr::read(""), "");
+    }
+    // A program drawing `drawing` on the CRT, one addx per pair of pixels with X
+    // placed to light just the right ones. X starts at 1, so the first two pixels
+    // must be lit.
+    fn program_for(drawing: &str) -> String {
+        let pixels = drawing
+            .lines()
+            .flat_map(|row| row.chars().map(|c| c == '#'))
+            .collect::<Vec<bool>>();
+        let xs = pixels
+            .chunks(2)
+            .enumerate()
+            .map(|(k, pair)| {
+                let column = (2 * k % CRT_WIDTH) as i32;
+                match (pair[0], pair[1]) {
+                    (true, true) => column + 1,
+                    (true, false) => column - 1,
+                    (false, true) => column + 2,
+                    (false, false) => column + 3,
+                }
+            })
+            .collect::<Vec<i32>>();
+        assert_eq!(xs[0], 1);
+        xs.iter()
+            .zip(xs.iter().skip(1).chain(xs.last()))
+            .map(|(x, next)| format!("addx {}\n", next - x))
+            .collect()
+    }
+
+    #[test]
+    fn test_part2_letters() {
+        // The puzzle's Y is five pixels wide, its last column landing where the
+        // small font leaves a gap between letters
+        let mut rows = spell(&ocr::SMALL, "ZEPHYR")
+            .lines()
+            .map(|row| format!("{row:.<40}"))
+            .collect::<Vec<String>>();
+        for row in &mut rows[..2] {
+            row.replace_range(24..25, "#");
+        }
+        let drawing = rows.join("\n");
+        assert!(drawing.starts_with("####.####.###..#..#.#...####."));
+        let program = program_for(&drawing);
+        assert_eq!(draw(&parse_program(&program, &BASE)).render(), drawing);
+        assert_eq!(part2(&program), "ZEPHYR");
+    }
+    #[test]
+    fn test_hook_sees_cycles() {
+        let program = parse_program("noop\naddx 3\naddx -5", &BASE);
+        let mut cpu = Cpu::new(&program);
+        let mut seen = vec![];
+        cpu.run(|cpu| seen.push((cpu.cycle, cpu.x())));
+        assert_eq!(seen, vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        assert_eq!(cpu.x(), -1);
+        assert!(cpu.halted());
+        assert!(!cpu.tick(|_| panic!("Ticked a halted CPU")));
+        let program = parse_program(&"noop\n".repeat(150), &BASE);
+        let mut sampled = vec![];
+        Cpu::new(&program).run(|cpu| sampled.extend(cpu.sampled_signal().map(|_| cpu.cycle)));
+        assert_eq!(sampled, vec![20, 60, 100, 140]);
+    }
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_instr("addy 1", &BASE).unwrap_err(),
+            "Invalid instruction"
+        );
+        assert_eq!(
+            parse_instr("addx", &BASE).unwrap_err(),
+            "Wrong number of operands"
+        );
+        assert_eq!(
+            parse_instr("noop 1", &BASE).unwrap_err(),
+            "Wrong number of operands"
+        );
+        assert_eq!(parse_instr("addx one", &BASE).unwrap_err(), "Not a number");
+        assert_eq!(parse_instr("addy 1", &EXTENDED).unwrap().args, vec![1]);
+    }
+    #[test]
+    fn test_extended() {
+        let run = |input| {
+            let program = parse_program(input, &EXTENDED);
+            let mut cpu = Cpu::new(&program);
+            cpu.run(|_| {});
+            (cpu.cycle, cpu.register('x'), cpu.register('y'))
+        };
+        assert_eq!(run("addy 5\naddx 1"), (4, 2, 5));
+        assert_eq!(run("jmp 2\naddx 100\naddx 1"), (3, 2, 0));
+        assert_eq!(run("jmp -5\naddx 1"), (1, 1, 0));
+        // Counts X down from 4 to 0, three cycles a loop
+        assert_eq!(run("addx 3\naddx -1\njnz -1"), (14, 0, 0));
+        // The base program behaves the same with the larger table
+        let program = parse_program(TEST_INPUT, &EXTENDED);
+        assert_eq!(signal_sum(&program), 13140);
+    }
+    // Feeds `script` to a debugger one command per line, collecting the replies
+    fn debug(input: &str, script: &str) -> Vec<Result<String, &'static str>> {
+        let program = parse_program(input, &EXTENDED);
+        let mut debugger = Debugger::new(&program);
+        script.lines().map(|line| debugger.command(line)).collect()
+    }
+
+    #[test]
+    fn test_debugger_breakpoints() {
+        let mut script = (20..=220)
+            .step_by(40)
+            .map(|n| format!("break cycle {n}\n"))
+            .collect::<String>();
+        script += "watch signal\nwatch sum\n";
+        script += &"continue\n".repeat(7);
+        let replies = debug(TEST_INPUT, &script);
+        let stops = replies[8..]
+            .iter()
+            .map(|r| r.as_ref().unwrap().lines().take(3).collect::<Vec<&str>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            stops[0],
+            [
+                "Breakpoint 1, Cycle 20: addx -1 at 10 (1/2), X = 21",
+                "signal = 420",
+                "sum = 0"
+            ]
+        );
+        let signals = stops[..6].iter().map(|stop| stop[1]).collect::<Vec<&str>>();
+        assert_eq!(
+            signals,
+            [
+                "signal = 420",
+                "signal = 1140",
+                "signal = 1800",
+                "signal = 2940",
+                "signal = 2880",
+                "signal = 3960"
+            ]
+        );
+        assert_eq!(stops[5][2], "sum = 9180");
+        assert_eq!(stops[6][0], "Halted after 240 cycles, X = 17");
+        assert_eq!(stops[6][2], "sum = 13140");
+        // The whole CRT is drawn once the program halts
+        let halted = replies[14].as_ref().unwrap();
+        assert!(halted.ends_with(&draw(&parse_program(TEST_INPUT, &BASE)).render()));
+    }
+    #[test]
+    fn test_debugger_stepping() {
+        let replies = debug(
+            TEST_INPUT,
+            "where\nstep\nnext\nstep 3\nb instr 5\nc\ncrt\np x",
+        );
+        let replies = replies
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(replies[0], "Cycle 1: addx 15 at 0 (1/2), X = 1");
+        assert_eq!(replies[1], "Cycle 2: addx 15 at 0 (2/2), X = 1\n#");
+        assert_eq!(replies[2], "Cycle 3: addx -11 at 1 (1/2), X = 16\n##");
+        assert_eq!(replies[3], "Cycle 6: addx 6 at 2 (2/2), X = 5\n##..#");
+        assert_eq!(replies[4], "Breakpoint 1 at instruction 5");
+        assert_eq!(
+            replies[5],
+            "Breakpoint 1, Cycle 11: addx -1 at 5 (1/2), X = 13\n##..##..##"
+        );
+        assert_eq!(replies[6], "##..##..##");
+        assert_eq!(replies[7], "x = 13");
+    }
+    #[test]
+    fn test_debugger_loop() {
+        // Stops at the top of the countdown loop until X reaches zero
+        let script = "b instr 1\nw y\ninfo\nc\nc\nc\nc\nc\ndelete 1\ndelete\nc";
+        let replies = debug("addx 3\naddx -1\njnz -1\naddy 2", script);
+        let replies = replies
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(replies[2], "Breakpoint 1: instruction 1\nWatching y");
+        let x = replies[3..8]
+            .iter()
+            .map(|r| r.lines().next().unwrap())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            x,
+            [
+                "Breakpoint 1, Cycle 3: addx -1 at 1 (1/2), X = 4",
+                "Breakpoint 1, Cycle 6: addx -1 at 1 (1/2), X = 3",
+                "Breakpoint 1, Cycle 9: addx -1 at 1 (1/2), X = 2",
+                "Breakpoint 1, Cycle 12: addx -1 at 1 (1/2), X = 1",
+                "Halted after 16 cycles, X = 0"
+            ]
+        );
+        assert!(replies[7].contains("\ny = 2\n"));
+        assert_eq!(replies[8], "Deleted breakpoint 1");
+        assert_eq!(replies[9], "Deleted all breakpoints");
+        assert!(replies[10].starts_with("Halted"));
+    }
+    #[test]
+    fn test_cycle_limit() {
+        let replies = debug("addx 2\njmp 0", "limit 50\nc\nc\nlimit 60\nw cycle\nc");
+        let replies = replies
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(replies[0], "Cycle limit set to 50");
+        assert!(replies[1].starts_with("Cycle limit of 50 reached, X = 3\n"));
+        assert_eq!(replies[2], replies[1]);
+        assert!(replies[5].starts_with("Cycle limit of 60 reached, X = 3\ncycle = 61\n"));
+        let program = parse_program("jmp 0", &EXTENDED);
+        let mut cpu = Cpu::new(&program);
+        cpu.run(|_| {});
+        assert!(cpu.at_limit());
+        assert_eq!(cpu.cycle, MAX_CYCLES);
+        // Stepping further than any cycle count stops at the limit instead
+        let replies = debug("jmp 0", &format!("limit 10\nstep {}", usize::MAX));
+        assert!(replies[1]
+            .as_ref()
+            .unwrap()
+            .starts_with("Cycle limit of 10 reached"));
+    }
+    #[test]
+    fn test_debugger_errors() {
+        let replies = debug(
+            "noop",
+            "\nbogus\nprint z\nwatch\ndelete 1\nbreak\nbreak cycle x\nstep -1",
+        );
+        assert_eq!(
+            replies,
+            [
+                Ok(String::new()),
+                Err("Unknown command"),
+                Err("Unknown expression"),
+                Err("Expected an expression"),
+                Err("No such breakpoint"),
+                Err("Expected cycle or instr after break"),
+                Err("Not a number"),
+                Err("Not a number")
+            ]
+        );
     }
 }
@@ -119,7 +119,7 @@ fn run_inspections(input: &str, rounds: i32, divisor: i64) -> u64 {
             }
         }
     }
-    monkeys.sort_by(|a, b| b.inspections.cmp(&a.inspections));
+    monkeys.sort_by_key(|m| std::cmp::Reverse(m.inspections));
     monkeys.iter().take(2).map(|m| m.inspections).product()
 }
 
@@ -57,7 +57,7 @@ impl FromStr for PacketData {
                     None => break,
                 }
             } else {
-                let next_comma = inner.find(|c| c == ',').unwrap_or(inner.len());
+                let next_comma = inner.find(',').unwrap_or(inner.len());
                 let num = inner[..next_comma].parse::<u32>().expect("Invalid number");
                 data.push(Self::Int(num));
                 inner = match &inner.get(next_comma + 1..) {
@@ -55,8 +55,8 @@ fn build_grid(
         }
     }
     if part2 {
-        for i in 0..dim_x {
-            grid[dim_y - 1][i] = Tile::Rock;
+        for tile in grid[dim_y - 1].iter_mut() {
+            *tile = Tile::Rock;
         }
     }
     grid[0][500 - start_x] = Tile::SandSource;
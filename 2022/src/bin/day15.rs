@@ -1,4 +1,4 @@
-use std::{fs, ops::RangeInclusive, str::FromStr, time::Duration};
+use std::{fs, ops::RangeInclusive, str::FromStr};
 
 trait RangeExt
 where
@@ -0,0 +1,357 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, ops,
+    str::FromStr,
+};
+
+const ROOT: &str = "root";
+const HUMAN: &str = "humn";
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Exact fraction, always stored reduced with a positive denominator
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    fn new(num: i128, den: i128) -> Result<Self, &'static str> {
+        if den == 0 {
+            return Err("Division by zero");
+        }
+        let g = gcd(num, den).max(1) * den.signum();
+        Ok(Self {
+            num: num / g,
+            den: den / g,
+        })
+    }
+    fn integer(&self) -> Option<i128> {
+        (self.den == 1).then_some(self.num)
+    }
+    fn checked_div(self, rhs: Self) -> Result<Self, &'static str> {
+        Self::new(self.num * rhs.den, self.den * rhs.num)
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(n: i64) -> Self {
+        Self {
+            num: n as i128,
+            den: 1,
+        }
+    }
+}
+
+impl ops::Add for Rational {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.num * rhs.den + rhs.num * self.den, self.den * rhs.den).unwrap()
+    }
+}
+
+impl ops::Sub for Rational {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.num * rhs.den - rhs.num * self.den, self.den * rhs.den).unwrap()
+    }
+}
+
+impl ops::Mul for Rational {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(self.num * rhs.num, self.den * rhs.den).unwrap()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Operator {
+    fn apply(&self, l: Rational, r: Rational) -> Result<Rational, &'static str> {
+        Ok(match self {
+            Operator::Add => l + r,
+            Operator::Sub => l - r,
+            Operator::Mul => l * r,
+            Operator::Div => l.checked_div(r)?,
+        })
+    }
+    // Solves `x op rhs = target` for x
+    fn solve_left(&self, target: Rational, rhs: Rational) -> Result<Rational, &'static str> {
+        Ok(match self {
+            Operator::Add => target - rhs,
+            Operator::Sub => target + rhs,
+            Operator::Mul => target.checked_div(rhs)?,
+            Operator::Div => target * rhs,
+        })
+    }
+    // Solves `lhs op x = target` for x
+    fn solve_right(&self, target: Rational, lhs: Rational) -> Result<Rational, &'static str> {
+        Ok(match self {
+            Operator::Add => target - lhs,
+            Operator::Sub => lhs - target,
+            Operator::Mul => target.checked_div(lhs)?,
+            Operator::Div => lhs.checked_div(target)?,
+        })
+    }
+}
+
+impl FromStr for Operator {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+" => Ok(Self::Add),
+            "-" => Ok(Self::Sub),
+            "*" => Ok(Self::Mul),
+            "/" => Ok(Self::Div),
+            _ => Err("Invalid operator"),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Job<'a> {
+    Number(i64),
+    Op(&'a str, Operator, &'a str),
+}
+
+impl<'a> TryFrom<&'a str> for Job<'a> {
+    type Error = &'static str;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        let mut parts = s.split_whitespace();
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(n), None, None, None) => {
+                Ok(Self::Number(n.parse().map_err(|_| "Invalid number")?))
+            }
+            (Some(l), Some(op), Some(r), None) => Ok(Self::Op(l, op.parse()?, r)),
+            _ => Err("Invalid job"),
+        }
+    }
+}
+
+struct Monkeys<'a> {
+    jobs: HashMap<&'a str, Job<'a>>,
+}
+
+impl<'a> Monkeys<'a> {
+    fn parse(input: &'a str) -> Result<Self, &'static str> {
+        let jobs = input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let (name, job) = line.split_once(':').ok_or("Missing colon")?;
+                Ok((name.trim(), Job::try_from(job)?))
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        let monkeys = Self { jobs };
+        monkeys.check_acyclic()?;
+        Ok(monkeys)
+    }
+
+    // Rejects monkeys whose value depends on itself, which would otherwise
+    // recurse forever. Depth-first from every monkey, without recursion.
+    fn check_acyclic(&self) -> Result<(), &'static str> {
+        let mut visiting = HashSet::new();
+        let mut done = HashSet::new();
+        for &start in self.jobs.keys() {
+            if done.contains(start) {
+                continue;
+            }
+            visiting.insert(start);
+            let mut stack = vec![(start, 0)]; // monkey and the next operand to visit
+            while let Some((name, next)) = stack.pop() {
+                let operand = match self.jobs.get(name) {
+                    Some(&Job::Op(l, _, r)) if next < 2 => Some([l, r][next]),
+                    _ => None,
+                };
+                let Some(operand) = operand else {
+                    visiting.remove(name);
+                    done.insert(name);
+                    continue;
+                };
+                stack.push((name, next + 1));
+                if visiting.contains(operand) {
+                    return Err("Monkey definitions form a cycle");
+                }
+                if !done.contains(operand) {
+                    visiting.insert(operand);
+                    stack.push((operand, 0));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn job(&self, name: &str) -> Result<&Job<'a>, &'static str> {
+        self.jobs.get(name).ok_or("Monkey not found")
+    }
+
+    fn eval(&self, name: &str) -> Result<Rational, &'static str> {
+        match *self.job(name)? {
+            Job::Number(n) => Ok(n.into()),
+            Job::Op(l, op, r) => op.apply(self.eval(l)?, self.eval(r)?),
+        }
+    }
+
+    // Records for each monkey whether its value depends on `unknown`
+    fn mark_dependents(&self, name: &'a str, unknown: &str, marks: &mut HashMap<&'a str, bool>) {
+        if marks.contains_key(name) {
+            return;
+        }
+        let depends = name == unknown
+            || match self.jobs.get(name) {
+                Some(&Job::Op(l, _, r)) => {
+                    self.mark_dependents(l, unknown, marks);
+                    self.mark_dependents(r, unknown, marks);
+                    marks[l] || marks[r]
+                }
+                _ => false,
+            };
+        marks.insert(name, depends);
+    }
+
+    // Finds the value `unknown` must have for `name` to evaluate to `target`
+    fn solve(
+        &self,
+        name: &str,
+        unknown: &str,
+        target: Rational,
+        marks: &HashMap<&str, bool>,
+    ) -> Result<Rational, &'static str> {
+        if name == unknown {
+            return Ok(target);
+        }
+        match *self.job(name)? {
+            Job::Number(_) => Err("Unknown not found in expression"),
+            Job::Op(l, op, r) => match (marks[l], marks[r]) {
+                (true, true) => Err("Unknown appears on both sides"),
+                (true, false) => {
+                    let target = op.solve_left(target, self.eval(r)?)?;
+                    self.solve(l, unknown, target, marks)
+                }
+                (false, true) => {
+                    let target = op.solve_right(target, self.eval(l)?)?;
+                    self.solve(r, unknown, target, marks)
+                }
+                (false, false) => Err("Unknown not found in expression"),
+            },
+        }
+    }
+
+    // Solves `lhs = rhs` of `root` for the value of `unknown`
+    fn solve_equality(&self, unknown: &str) -> Result<Rational, &'static str> {
+        let &Job::Op(l, _, r) = self.job(ROOT)? else {
+            return Err("Root is not an operation");
+        };
+        let mut marks = HashMap::new();
+        self.mark_dependents(ROOT, unknown, &mut marks);
+        match (marks[l], marks[r]) {
+            (true, true) => Err("Unknown appears on both sides"),
+            (true, false) => self.solve(l, unknown, self.eval(r)?, &marks),
+            (false, true) => self.solve(r, unknown, self.eval(l)?, &marks),
+            (false, false) => Err("Unknown not found in expression"),
+        }
+    }
+}
+
+fn to_integer(value: Rational) -> Result<i64, &'static str> {
+    value
+        .integer()
+        .and_then(|n| n.try_into().ok())
+        .ok_or("Result is not an integer")
+}
+
+fn part1(input: &str) -> Result<i64, &'static str> {
+    let monkeys = Monkeys::parse(input)?;
+    to_integer(monkeys.eval(ROOT)?)
+}
+
+fn part2(input: &str) -> Result<i64, &'static str> {
+    let monkeys = Monkeys::parse(input)?;
+    to_integer(monkeys.solve_equality(HUMAN)?)
+}
+
+fn main() {
+    let input = fs::read_to_string("inputs/day21.txt").expect("Could not read input file");
+    let part1 = part1(&input).expect("Could not evaluate root");
+    let part2 = part2(&input).expect("Could not solve for humn");
+    println!("Part 1: {part1}");
+    println!("Part 2: {part2}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = "\
+root: pppw + sjmn
+dbpl: 5
+cczh: sllz + lgvd
+zczc: 2
+ptdq: humn - dvpt
+dvpt: 3
+lfqf: 4
+humn: 5
+ljgn: 2
+sjmn: drzm * dbpl
+sllz: 4
+pppw: cczh / lfqf
+lgvd: ljgn * ptdq
+drzm: hmdt - zczc
+hmdt: 32";
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(TEST_INPUT), Ok(152));
+    }
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(TEST_INPUT), Ok(301));
+    }
+    #[test]
+    fn test_fractional_intermediate() {
+        // humn / 4 = 3 / 2 only holds for humn = 6, which truncating division would miss
+        let input = "\
+root: left + right
+left: humn / four
+four: 4
+right: three / two
+three: 3
+two: 2
+humn: 1";
+        let monkeys = Monkeys::parse(input).unwrap();
+        assert_eq!(monkeys.eval("right"), Rational::new(3, 2));
+        assert_eq!(monkeys.solve_equality(HUMAN), Ok(6.into()));
+    }
+    #[test]
+    fn test_unknown_on_both_sides() {
+        let input = "\
+root: left + right
+left: humn * two
+right: humn + two
+two: 2
+humn: 1";
+        assert_eq!(part2(input), Err("Unknown appears on both sides"));
+    }
+    #[test]
+    fn test_cycles() {
+        let input = "root: left + right\nleft: right * two\nright: left - two\ntwo: 2";
+        assert_eq!(part1(input), Err("Monkey definitions form a cycle"));
+        assert_eq!(part2(input), Err("Monkey definitions form a cycle"));
+        assert!(Monkeys::parse("root: root + root").is_err());
+        // Sharing an operand is not a cycle
+        assert_eq!(part1("root: two * two\ntwo: 2"), Ok(4));
+    }
+}
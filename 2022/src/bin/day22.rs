@@ -0,0 +1,440 @@
+use std::{collections::VecDeque, fs, ops::Neg};
+
+type Pos = (usize, usize); // (row, col)
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tile {
+    Void,
+    Open,
+    Wall,
+}
+
+impl TryFrom<char> for Tile {
+    type Error = &'static str;
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            ' ' => Ok(Tile::Void),
+            '.' => Ok(Tile::Open),
+            '#' => Ok(Tile::Wall),
+            _ => Err("Invalid tile"),
+        }
+    }
+}
+
+// Facing values are the ones used for the password: right, down, left, up
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Facing(usize);
+
+impl Facing {
+    const RIGHT: Self = Self(0);
+    const DOWN: Self = Self(1);
+    const LEFT: Self = Self(2);
+    const UP: Self = Self(3);
+    const ALL: [Self; 4] = [Self::RIGHT, Self::DOWN, Self::LEFT, Self::UP];
+
+    fn turn_right(self) -> Self {
+        Self((self.0 + 1) % 4)
+    }
+    fn turn_left(self) -> Self {
+        Self((self.0 + 3) % 4)
+    }
+    fn reverse(self) -> Self {
+        Self((self.0 + 2) % 4)
+    }
+    fn delta(self) -> (isize, isize) {
+        [(0, 1), (1, 0), (0, -1), (-1, 0)][self.0]
+    }
+    fn is_horizontal(self) -> bool {
+        self == Self::RIGHT || self == Self::LEFT
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Step {
+    Forward(usize),
+    Left,
+    Right,
+}
+
+fn parse_path(s: &str) -> Result<Vec<Step>, &'static str> {
+    let mut steps = vec![];
+    let mut num: Option<usize> = None;
+    for c in s.trim().chars() {
+        if let Some(d) = c.to_digit(10) {
+            num = Some(num.unwrap_or(0) * 10 + d as usize);
+            continue;
+        }
+        if let Some(n) = num.take() {
+            steps.push(Step::Forward(n));
+        }
+        steps.push(match c {
+            'L' => Step::Left,
+            'R' => Step::Right,
+            _ => return Err("Invalid step in path"),
+        });
+    }
+    if let Some(n) = num {
+        steps.push(Step::Forward(n));
+    }
+    Ok(steps)
+}
+
+struct Board {
+    tiles: Vec<Vec<Tile>>,
+}
+
+impl Board {
+    fn parse(s: &str) -> Result<Self, &'static str> {
+        let width = s.lines().map(str::len).max().unwrap_or(0);
+        let tiles = s
+            .lines()
+            .map(|line| {
+                let mut row = line
+                    .chars()
+                    .map(Tile::try_from)
+                    .collect::<Result<Vec<Tile>, _>>()?;
+                row.resize(width, Tile::Void);
+                Ok(row)
+            })
+            .collect::<Result<Vec<Vec<Tile>>, _>>()?;
+        Ok(Self { tiles })
+    }
+
+    fn height(&self) -> usize {
+        self.tiles.len()
+    }
+    fn width(&self) -> usize {
+        self.tiles.first().map_or(0, Vec::len)
+    }
+
+    fn get(&self, (row, col): Pos) -> Tile {
+        self.tiles
+            .get(row)
+            .and_then(|r| r.get(col))
+            .copied()
+            .unwrap_or(Tile::Void)
+    }
+
+    fn start(&self) -> Option<Pos> {
+        let col = self.tiles.first()?.iter().position(|&t| t == Tile::Open)?;
+        Some((0, col))
+    }
+
+    // Neighbouring position on the flat board, None if it would leave the grid
+    fn next_flat(&self, (row, col): Pos, facing: Facing) -> Option<Pos> {
+        let (dr, dc) = facing.delta();
+        let row = row.checked_add_signed(dr).filter(|&r| r < self.height())?;
+        let col = col.checked_add_signed(dc).filter(|&c| c < self.width())?;
+        Some((row, col))
+    }
+
+    // Part 1 wrapping: come back in from the opposite side of the row or column
+    fn wrap_flat(&self, pos: Pos, facing: Facing) -> (Pos, Facing) {
+        let back = facing.reverse();
+        let mut pos = pos;
+        while let Some(prev) = self
+            .next_flat(pos, back)
+            .filter(|&p| self.get(p) != Tile::Void)
+        {
+            pos = prev;
+        }
+        (pos, facing)
+    }
+
+    fn walk(&self, path: &[Step], wrap: impl Fn(Pos, Facing) -> (Pos, Facing)) -> (Pos, Facing) {
+        let mut pos = self.start().expect("No open tile on the first row");
+        let mut facing = Facing::RIGHT;
+        for step in path {
+            match step {
+                Step::Left => facing = facing.turn_left(),
+                Step::Right => facing = facing.turn_right(),
+                Step::Forward(n) => {
+                    for _ in 0..*n {
+                        let (next, next_facing) = match self.next_flat(pos, facing) {
+                            Some(p) if self.get(p) != Tile::Void => (p, facing),
+                            _ => wrap(pos, facing),
+                        };
+                        if self.get(next) == Tile::Wall {
+                            break;
+                        }
+                        (pos, facing) = (next, next_facing);
+                    }
+                }
+            }
+        }
+        (pos, facing)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Vec3([i32; 3]);
+
+impl Neg for Vec3 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self(self.0.map(|v| -v))
+    }
+}
+
+// A face of the cube placed in 3D: `x` and `y` point along increasing column
+// and row of the net, `normal` points out of the cube.
+#[derive(Clone, Copy, Debug)]
+struct Face {
+    block: Pos,
+    x: Vec3,
+    y: Vec3,
+    normal: Vec3,
+}
+
+impl Face {
+    // Direction in 3D of moving off the face with the given facing
+    fn towards(&self, facing: Facing) -> Vec3 {
+        match facing {
+            Facing::RIGHT => self.x,
+            Facing::DOWN => self.y,
+            Facing::LEFT => -self.x,
+            _ => -self.y,
+        }
+    }
+    // Face placement after folding over the edge in the given facing
+    fn folded(&self, block: Pos, facing: Facing) -> Self {
+        let normal = self.towards(facing);
+        let inward = -self.normal;
+        let (x, y) = match facing {
+            Facing::RIGHT => (inward, self.y),
+            Facing::DOWN => (self.x, inward),
+            Facing::LEFT => (self.normal, self.y),
+            _ => (self.x, self.normal),
+        };
+        Self {
+            block,
+            x,
+            y,
+            normal,
+        }
+    }
+}
+
+struct Cube {
+    size: usize,
+    faces: Vec<Face>,
+}
+
+impl Cube {
+    // Folds the net of the board into a cube
+    fn fold(board: &Board) -> Result<Self, &'static str> {
+        let area = board
+            .tiles
+            .iter()
+            .flatten()
+            .filter(|&&t| t != Tile::Void)
+            .count();
+        let size = (1..=area).find(|s| 6 * s * s >= area).unwrap_or(0);
+        if size == 0 || 6 * size * size != area {
+            return Err("Board area is not that of a cube");
+        }
+
+        let blocks = (0..board.height() / size)
+            .flat_map(|r| (0..board.width() / size).map(move |c| (r, c)))
+            .filter(|&(r, c)| board.get((r * size, c * size)) != Tile::Void)
+            .collect::<Vec<Pos>>();
+        let first = *blocks.first().ok_or("Empty board")?;
+
+        let mut faces = vec![Face {
+            block: first,
+            x: Vec3([1, 0, 0]),
+            y: Vec3([0, 1, 0]),
+            normal: Vec3([0, 0, -1]),
+        }];
+        let mut queue = VecDeque::from([faces[0]]);
+        while let Some(face) = queue.pop_front() {
+            for facing in Facing::ALL {
+                let (dr, dc) = facing.delta();
+                let Some(block) = face
+                    .block
+                    .0
+                    .checked_add_signed(dr)
+                    .zip(face.block.1.checked_add_signed(dc))
+                    .filter(|b| blocks.contains(b))
+                else {
+                    continue;
+                };
+                if faces.iter().all(|f| f.block != block) {
+                    let folded = face.folded(block, facing);
+                    faces.push(folded);
+                    queue.push_back(folded);
+                }
+            }
+        }
+
+        let distinct_normals = faces
+            .iter()
+            .enumerate()
+            .all(|(i, f)| faces[..i].iter().all(|g| g.normal != f.normal));
+        if faces.len() != 6 || blocks.len() != 6 || !distinct_normals {
+            return Err("Board is not a cube net");
+        }
+        Ok(Self { size, faces })
+    }
+
+    fn face_at(&self, (row, col): Pos) -> &Face {
+        let block = (row / self.size, col / self.size);
+        self.faces
+            .iter()
+            .find(|f| f.block == block)
+            .expect("Position is not on a face")
+    }
+
+    // Part 2 wrapping: continue onto the adjacent face of the cube
+    fn wrap(&self, pos: Pos, facing: Facing) -> (Pos, Facing) {
+        let n = self.size;
+        let from = self.face_at(pos);
+        let (row, col) = (pos.0 % n, pos.1 % n);
+        let (offset, along) = if facing.is_horizontal() {
+            (row, from.y)
+        } else {
+            (col, from.x)
+        };
+
+        let to = self
+            .faces
+            .iter()
+            .find(|f| f.normal == from.towards(facing))
+            .expect("Missing cube face");
+        let side = Facing::ALL
+            .into_iter()
+            .find(|&s| to.towards(s) == from.normal)
+            .expect("Faces are not adjacent");
+        let to_along = if side.is_horizontal() { to.y } else { to.x };
+        let offset = if to_along == along {
+            offset
+        } else {
+            n - 1 - offset
+        };
+
+        let (row, col) = match side {
+            Facing::RIGHT => (offset, n - 1),
+            Facing::DOWN => (n - 1, offset),
+            Facing::LEFT => (offset, 0),
+            _ => (0, offset),
+        };
+        ((to.block.0 * n + row, to.block.1 * n + col), side.reverse())
+    }
+}
+
+fn parse_input(input: &str) -> (Board, Vec<Step>) {
+    let (board, path) = input.split_once("\n\n").expect("Missing path");
+    (
+        Board::parse(board).expect("Invalid board"),
+        parse_path(path).expect("Invalid path"),
+    )
+}
+
+fn password(((row, col), facing): (Pos, Facing)) -> usize {
+    1000 * (row + 1) + 4 * (col + 1) + facing.0
+}
+
+fn part1(input: &str) -> usize {
+    let (board, path) = parse_input(input);
+    password(board.walk(&path, |pos, facing| board.wrap_flat(pos, facing)))
+}
+
+fn part2(input: &str) -> usize {
+    let (board, path) = parse_input(input);
+    let cube = Cube::fold(&board).expect("Could not fold board into a cube");
+    password(board.walk(&path, |pos, facing| cube.wrap(pos, facing)))
+}
+
+fn main() {
+    let input = fs::read_to_string("inputs/day22.txt").expect("Could not read input file");
+    let part1 = part1(&input);
+    let part2 = part2(&input);
+    println!("Part 1: {part1}");
+    println!("Part 2: {part2}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = "        ...#
+        .#..
+        #...
+        ....
+...#.......#
+........#...
+..#....#....
+..........#.
+        ...#....
+        .....#..
+        .#......
+        ......#.
+
+10R5L5R10L4R5L5";
+
+    // Builds an empty board from a net where each character marks one face
+    fn empty_net(net: &str, size: usize) -> Board {
+        let board = net
+            .lines()
+            .flat_map(|line| {
+                let row = line
+                    .chars()
+                    .map(|c| if c == ' ' { " " } else { "." }.repeat(size))
+                    .collect::<String>();
+                vec![row; size]
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        Board::parse(&board).unwrap()
+    }
+
+    // Walking 4 edge lengths in a straight line on an empty cube must lead back
+    // to the start, and stepping back across any edge must undo the step.
+    fn check_net(net: &str, size: usize) {
+        let board = empty_net(net, size);
+        let cube = Cube::fold(&board).unwrap();
+        for face in cube.faces.iter() {
+            for (row, col) in [(0, 0), (0, size - 1), (size / 2, size - 1), (size - 1, 1)] {
+                let start = (face.block.0 * size + row, face.block.1 * size + col);
+                for facing in Facing::ALL {
+                    let (mut pos, mut dir) = (start, facing);
+                    for _ in 0..4 * size {
+                        (pos, dir) = match board.next_flat(pos, dir) {
+                            Some(p) if board.get(p) != Tile::Void => (p, dir),
+                            _ => {
+                                let (next, next_dir) = cube.wrap(pos, dir);
+                                assert_eq!(
+                                    cube.wrap(next, next_dir.reverse()),
+                                    (pos, dir.reverse())
+                                );
+                                (next, next_dir)
+                            }
+                        };
+                    }
+                    assert_eq!((pos, dir), (start, facing), "net:\n{net}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(TEST_INPUT), 6032);
+    }
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(TEST_INPUT), 5031);
+    }
+    #[test]
+    fn test_cube_nets() {
+        check_net("  #\n###\n  ##", 4);
+        check_net(" ##\n #\n##\n#", 4);
+        check_net(" #\n####\n #", 3);
+        check_net("##\n ###\n   #", 5);
+    }
+    #[test]
+    fn test_invalid_net() {
+        assert!(Cube::fold(&empty_net("######", 2)).is_err());
+        assert!(Cube::fold(&empty_net("###\n###", 2)).is_err());
+    }
+}
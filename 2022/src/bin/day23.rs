@@ -0,0 +1,228 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fmt::Display,
+    fs,
+};
+
+type Pos = (i64, i64); // (x, y), y grows downwards
+
+#[derive(Clone, Copy, Debug)]
+enum Dir {
+    N,
+    S,
+    W,
+    E,
+}
+
+impl Dir {
+    // The three neighbouring offsets that have to be empty to move in this direction,
+    // the middle one being the move itself
+    fn offsets(&self) -> [Pos; 3] {
+        match self {
+            Dir::N => [(-1, -1), (0, -1), (1, -1)],
+            Dir::S => [(-1, 1), (0, 1), (1, 1)],
+            Dir::W => [(-1, -1), (-1, 0), (-1, 1)],
+            Dir::E => [(1, -1), (1, 0), (1, 1)],
+        }
+    }
+}
+
+const DIRECTIONS: [Dir; 4] = [Dir::N, Dir::S, Dir::W, Dir::E];
+
+const NEIGHBOURS: [Pos; 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+fn add((x, y): Pos, (dx, dy): Pos) -> Pos {
+    (x + dx, y + dy)
+}
+
+struct Grove {
+    elves: HashSet<Pos>,
+    round: usize,
+}
+
+impl Grove {
+    fn parse(input: &str) -> Self {
+        let elves = input
+            .lines()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(x, _)| (x as i64, y as i64))
+            })
+            .collect();
+        Self { elves, round: 0 }
+    }
+
+    fn proposal(&self, elf: Pos) -> Option<Pos> {
+        if NEIGHBOURS
+            .iter()
+            .all(|&d| !self.elves.contains(&add(elf, d)))
+        {
+            return None;
+        }
+        (0..DIRECTIONS.len())
+            .map(|i| DIRECTIONS[(self.round + i) % DIRECTIONS.len()].offsets())
+            .find(|offsets| offsets.iter().all(|&d| !self.elves.contains(&add(elf, d))))
+            .map(|offsets| add(elf, offsets[1]))
+    }
+
+    // Runs one round, returns whether any elf moved
+    fn step(&mut self) -> bool {
+        let mut proposals = HashMap::<Pos, Vec<Pos>>::new();
+        for &elf in self.elves.iter() {
+            if let Some(target) = self.proposal(elf) {
+                proposals.entry(target).or_default().push(elf);
+            }
+        }
+        let mut moved = false;
+        for (target, elves) in proposals {
+            if let [elf] = elves[..] {
+                self.elves.remove(&elf);
+                self.elves.insert(target);
+                moved = true;
+            }
+        }
+        self.round += 1;
+        moved
+    }
+
+    // Smallest rectangle containing every elf as (min, max) corners
+    fn bounds(&self) -> Option<(Pos, Pos)> {
+        let xs = self.elves.iter().map(|e| e.0);
+        let ys = self.elves.iter().map(|e| e.1);
+        Some((
+            (xs.clone().min()?, ys.clone().min()?),
+            (xs.max()?, ys.max()?),
+        ))
+    }
+
+    fn empty_ground(&self) -> usize {
+        self.bounds().map_or(0, |((min_x, min_y), (max_x, max_y))| {
+            ((max_x - min_x + 1) * (max_y - min_y + 1)) as usize - self.elves.len()
+        })
+    }
+}
+
+impl Display for Grove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(((min_x, min_y), (max_x, max_y))) = self.bounds() else {
+            return Ok(());
+        };
+        let rows = (min_y..=max_y)
+            .map(|y| {
+                (min_x..=max_x)
+                    .map(|x| {
+                        if self.elves.contains(&(x, y)) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>();
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+// Runs rounds until `stop` returns true or the elves stop moving,
+// printing the map after every round if `render` is set
+fn simulate(input: &str, render: bool, stop: impl Fn(&Grove) -> bool) -> Grove {
+    let mut grove = Grove::parse(input);
+    if render {
+        println!("== Initial State ==\n{grove}\n");
+    }
+    while !stop(&grove) {
+        let moved = grove.step();
+        if render {
+            println!("== End of Round {} ==\n{grove}\n", grove.round);
+        }
+        if !moved {
+            break;
+        }
+    }
+    grove
+}
+
+fn part1(input: &str, render: bool) -> usize {
+    simulate(input, render, |grove| grove.round == 10).empty_ground()
+}
+
+fn part2(input: &str, render: bool) -> usize {
+    simulate(input, render, |_| false).round
+}
+
+fn main() {
+    let input = fs::read_to_string("inputs/day23.txt").expect("Could not read input file");
+    let render = env::args().any(|arg| arg == "--render");
+    let part1 = part1(&input, render);
+    let part2 = part2(&input, render);
+    println!("Part 1: {part1}");
+    println!("Part 2: {part2}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = "\
+....#..
+..###.#
+#...#.#
+.#...##
+#.###..
+##.#.##
+.#..#..";
+
+    const SMALL_INPUT: &str = "\
+.....
+..##.
+..#..
+.....
+..##.
+.....";
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(TEST_INPUT, false), 110);
+    }
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(TEST_INPUT, false), 20);
+    }
+    #[test]
+    fn test_render() {
+        let mut grove = Grove::parse(SMALL_INPUT);
+        assert_eq!(grove.to_string(), "##\n#.\n..\n##");
+        grove.step();
+        assert_eq!(grove.to_string(), "##\n..\n#.\n.#\n#.");
+        grove.step();
+        grove.step();
+        assert_eq!(
+            grove.to_string(),
+            "..#..\n....#\n#....\n....#\n.....\n..#.."
+        );
+        assert!(!grove.step());
+    }
+    #[test]
+    fn test_unbounded_growth() {
+        // A large cluster spreads past the origin in every direction
+        let input = vec!["#".repeat(8); 8].join("\n");
+        let grove = simulate(&input, false, |grove| grove.round == 50);
+        let ((min_x, min_y), (max_x, max_y)) = grove.bounds().unwrap();
+        assert!(min_x < 0 && min_y < 0 && max_x > 7 && max_y > 7);
+        assert_eq!(grove.elves.len(), 64);
+    }
+}
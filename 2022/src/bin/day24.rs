@@ -0,0 +1,185 @@
+use aoc::search::bfs;
+use std::fs;
+
+type Pos = (usize, usize); // (x, y) including the surrounding wall
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Blizzards are stored by their starting position inside the walls, which
+// makes the occupancy of any cell a function of time alone.
+struct Basin {
+    width: usize,
+    height: usize,
+    start: Pos,
+    end: Pos,
+    right: Vec<Vec<bool>>, // [y][x]
+    left: Vec<Vec<bool>>,
+    down: Vec<Vec<bool>>,
+    up: Vec<Vec<bool>>,
+}
+
+impl Basin {
+    fn parse(input: &str) -> Result<Self, &'static str> {
+        let lines = input.lines().map(str::as_bytes).collect::<Vec<&[u8]>>();
+        if lines.len() < 3 || lines[0].len() < 3 {
+            return Err("Basin too small");
+        }
+        if lines.iter().any(|line| line.len() != lines[0].len()) {
+            return Err("Ragged basin row");
+        }
+        let width = lines[0].len() - 2;
+        let height = lines.len() - 2;
+        // The openings must lead into the basin, not sit in a corner
+        let gap = |line: &[u8]| {
+            line[1..=width]
+                .iter()
+                .position(|&b| b == b'.')
+                .map(|x| x + 1)
+                .ok_or("Missing opening")
+        };
+        let start = (gap(lines[0])?, 0);
+        let end = (gap(lines[height + 1])?, height + 1);
+
+        let mut basin = Self {
+            width,
+            height,
+            start,
+            end,
+            right: vec![vec![false; width]; height],
+            left: vec![vec![false; width]; height],
+            down: vec![vec![false; width]; height],
+            up: vec![vec![false; width]; height],
+        };
+        for (y, line) in lines[1..=height].iter().enumerate() {
+            for (x, &b) in line[1..=width].iter().enumerate() {
+                match b {
+                    b'>' => basin.right[y][x] = true,
+                    b'<' => basin.left[y][x] = true,
+                    b'v' => basin.down[y][x] = true,
+                    b'^' => basin.up[y][x] = true,
+                    b'.' => {}
+                    _ => return Err("Invalid tile in basin"),
+                }
+            }
+        }
+        Ok(basin)
+    }
+
+    // Blizzard positions repeat after this many minutes
+    fn period(&self) -> usize {
+        self.width * self.height / gcd(self.width, self.height)
+    }
+
+    fn is_free(&self, (x, y): Pos, time: usize) -> bool {
+        if (x, y) == self.start || (x, y) == self.end {
+            return true;
+        }
+        if x == 0 || y == 0 || x > self.width || y > self.height {
+            return false;
+        }
+        let (x, y) = (x - 1, y - 1);
+        let (w, h) = (self.width, self.height);
+        !(self.right[y][(x + w - time % w) % w]
+            || self.left[y][(x + time) % w]
+            || self.down[(y + h - time % h) % h][x]
+            || self.up[(y + time) % h][x])
+    }
+
+    fn moves(&self, (x, y): Pos) -> impl Iterator<Item = Pos> {
+        [(0, 0), (1, 0), (0, 1), (-1, 0), (0, -1)]
+            .into_iter()
+            .flat_map(move |(dx, dy)| Some((x.checked_add_signed(dx)?, y.checked_add_signed(dy)?)))
+    }
+
+    // Minutes needed to get from `from` to `to` when leaving at `time`
+    fn crossing(&self, from: Pos, to: Pos, time: usize) -> Option<usize> {
+        let period = self.period();
+        let start = (from, time % period);
+        let (_, minutes) = bfs(
+            start,
+            |&(pos, t)| {
+                let t = (t + 1) % period;
+                self.moves(pos)
+                    .filter(move |&next| self.is_free(next, t))
+                    .map(move |next| (next, t))
+                    .collect::<Vec<_>>()
+            },
+            |&(pos, _)| pos == to,
+        )?;
+        Some(minutes)
+    }
+}
+
+fn part1(input: &str) -> usize {
+    let basin = Basin::parse(input).expect("Invalid input");
+    basin
+        .crossing(basin.start, basin.end, 0)
+        .expect("No path found")
+}
+
+fn part2(input: &str) -> usize {
+    let basin = Basin::parse(input).expect("Invalid input");
+    let legs = [
+        (basin.start, basin.end),
+        (basin.end, basin.start),
+        (basin.start, basin.end),
+    ];
+    legs.iter().fold(0, |time, &(from, to)| {
+        time + basin.crossing(from, to, time).expect("No path found")
+    })
+}
+
+fn main() {
+    let input = fs::read_to_string("inputs/day24.txt").expect("Could not read input file");
+    let part1 = part1(&input);
+    let part2 = part2(&input);
+    println!("Part 1: {part1}");
+    println!("Part 2: {part2}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = "\
+#.######
+#>>.<^<#
+#.<..<<#
+#>v.><>#
+#<^v^^>#
+######.#";
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(TEST_INPUT), 18);
+    }
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(TEST_INPUT), 54);
+    }
+    #[test]
+    fn test_blizzard_period() {
+        let basin = Basin::parse(TEST_INPUT).unwrap();
+        assert_eq!(basin.period(), 12);
+        for y in 0..=basin.height + 1 {
+            for x in 0..=basin.width + 1 {
+                assert_eq!(basin.is_free((x, y), 5), basin.is_free((x, y), 5 + 12));
+            }
+        }
+    }
+    #[test]
+    fn test_malformed_basin() {
+        assert_eq!(Basin::parse("").err(), Some("Basin too small"));
+        assert_eq!(Basin::parse("#.\n..\n.#").err(), Some("Basin too small"));
+        assert_eq!(Basin::parse("\n\n\n").err(), Some("Basin too small"));
+        assert_eq!(Basin::parse("#.#\n#.\n#.#").err(), Some("Ragged basin row"));
+        assert_eq!(Basin::parse(".##\n#.#\n#.#").err(), Some("Missing opening"));
+        assert!(Basin::parse("#.#\n#.#\n#.#").is_ok());
+    }
+}
@@ -0,0 +1,253 @@
+use std::{fmt::Display, fs, iter::Sum, ops, str::FromStr};
+
+// Balanced base-5 number with digits -2..=2, least significant digit first.
+// Kept as digits so that arbitrarily large sums can be represented.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Snafu {
+    digits: Vec<i8>,
+}
+
+impl Snafu {
+    fn from_digits(mut digits: Vec<i8>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Self { digits }
+    }
+    fn to_i128(&self) -> Option<i128> {
+        self.digits.iter().rev().try_fold(0i128, |acc, &d| {
+            // Borrow a 5 from the accumulator when the digit has the other
+            // sign, so acc * 5 stays in range whenever the result does
+            let (acc, d) = match (acc.signum(), d.signum()) {
+                (-1, 1) => (acc + 1, d - 5),
+                (1, -1) => (acc - 1, d + 5),
+                _ => (acc, d),
+            };
+            acc.checked_mul(5)?.checked_add(d as i128)
+        })
+    }
+}
+
+impl From<i128> for Snafu {
+    fn from(mut n: i128) -> Self {
+        let mut digits = vec![];
+        while n != 0 {
+            // Digits 3 and 4 become -2 and -1 with a carry into the next place
+            let r = n.rem_euclid(5);
+            digits.push(((r + 2) % 5 - 2) as i8);
+            n = n.div_euclid(5) + (r > 2) as i128;
+        }
+        Self { digits }
+    }
+}
+
+impl From<i64> for Snafu {
+    fn from(n: i64) -> Self {
+        Self::from(n as i128)
+    }
+}
+
+impl TryFrom<&Snafu> for i128 {
+    type Error = &'static str;
+    fn try_from(s: &Snafu) -> Result<Self, Self::Error> {
+        s.to_i128().ok_or("SNAFU number too large for i128")
+    }
+}
+
+impl TryFrom<&Snafu> for i64 {
+    type Error = &'static str;
+    fn try_from(s: &Snafu) -> Result<Self, Self::Error> {
+        s.to_i128()
+            .and_then(|n| n.try_into().ok())
+            .ok_or("SNAFU number too large for i64")
+    }
+}
+
+impl FromStr for Snafu {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s
+            .trim()
+            .chars()
+            .rev()
+            .map(|c| match c {
+                '=' => Ok(-2),
+                '-' => Ok(-1),
+                '0' => Ok(0),
+                '1' => Ok(1),
+                '2' => Ok(2),
+                _ => Err("Invalid SNAFU digit"),
+            })
+            .collect::<Result<Vec<i8>, _>>()?;
+        if digits.is_empty() {
+            return Err("Empty SNAFU number");
+        }
+        Ok(Self::from_digits(digits))
+    }
+}
+
+impl Display for Snafu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.digits.is_empty() {
+            return write!(f, "0");
+        }
+        let s = self
+            .digits
+            .iter()
+            .rev()
+            .map(|d| match d {
+                -2 => '=',
+                -1 => '-',
+                0 => '0',
+                1 => '1',
+                _ => '2',
+            })
+            .collect::<String>();
+        write!(f, "{}", s)
+    }
+}
+
+impl ops::Add<&Snafu> for &Snafu {
+    type Output = Snafu;
+    fn add(self, rhs: &Snafu) -> Snafu {
+        let len = self.digits.len().max(rhs.digits.len());
+        let mut digits = Vec::with_capacity(len + 1);
+        let mut carry = 0;
+        for i in 0..len {
+            let sum = self.digits.get(i).unwrap_or(&0) + rhs.digits.get(i).unwrap_or(&0) + carry;
+            carry = (sum + 2).div_euclid(5);
+            digits.push(sum - 5 * carry);
+        }
+        digits.push(carry);
+        Snafu::from_digits(digits)
+    }
+}
+
+impl ops::Add for Snafu {
+    type Output = Snafu;
+    fn add(self, rhs: Snafu) -> Snafu {
+        &self + &rhs
+    }
+}
+
+impl Sum for Snafu {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Snafu::default(), |acc, n| acc + n)
+    }
+}
+
+fn part1(input: &str) -> String {
+    input
+        .lines()
+        .map(|line| Snafu::from_str(line).expect("Invalid input"))
+        .sum::<Snafu>()
+        .to_string()
+}
+
+fn main() {
+    let input = fs::read_to_string("inputs/day25.txt").expect("Could not read input file");
+    let part1 = part1(&input);
+    println!("Part 1: {part1}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = "\
+1=-0-2
+12111
+2=0=
+21
+2=01
+111
+20012
+112
+1=-1=
+1-12
+12
+1=
+122";
+
+    // Numbers around every power of 5 that fits in an i64, where digits carry,
+    // and around the all-2 numbers halfway between them, plus the i64 limits
+    fn boundary_numbers() -> Vec<i64> {
+        let powers = (0..=27).map(|k| 5i64.pow(k));
+        let mut numbers = powers
+            .flat_map(|p| [p - 1, p, p + 1, p / 2, p / 2 + 1])
+            .flat_map(|n| [n, -n])
+            .collect::<Vec<i64>>();
+        numbers.extend([i64::MIN, i64::MIN + 1, i64::MAX - 1, i64::MAX]);
+        numbers
+    }
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(TEST_INPUT), "2=-1=0");
+    }
+    #[test]
+    fn test_conversion() {
+        let pairs = [
+            (1i64, "1"),
+            (3, "1="),
+            (8, "2="),
+            (2022, "1=11-2"),
+            (12345, "1-0---0"),
+            (314159265, "1121-1110-1=0"),
+            (-3, "-2"),
+        ];
+        for (n, s) in pairs {
+            assert_eq!(Snafu::from(n).to_string(), s);
+            assert_eq!(i64::try_from(&s.parse::<Snafu>().unwrap()), Ok(n));
+        }
+        assert_eq!(Snafu::from(0i64).to_string(), "0");
+        assert_eq!("00".parse::<Snafu>(), Ok(Snafu::default()));
+        assert!("12a".parse::<Snafu>().is_err());
+        assert!("".parse::<Snafu>().is_err());
+    }
+    #[test]
+    fn test_round_trip() {
+        for n in (-1000..=1000).chain(boundary_numbers()) {
+            let snafu = Snafu::from(n);
+            assert_eq!(i64::try_from(&snafu), Ok(n));
+            assert_eq!(snafu.to_string().parse::<Snafu>(), Ok(snafu));
+            let wide = n as i128 * i64::MAX as i128;
+            assert_eq!(i128::try_from(&Snafu::from(wide)), Ok(wide));
+        }
+        for n in [i128::MIN, i128::MIN + 1, i128::MAX - 1, i128::MAX] {
+            let snafu = Snafu::from(n);
+            assert_eq!(i128::try_from(&snafu), Ok(n));
+            assert_eq!(snafu.to_string().parse::<Snafu>(), Ok(snafu));
+        }
+        let beyond = Snafu::from(i128::MAX) + Snafu::from(1i64);
+        assert!(i128::try_from(&beyond).is_err());
+    }
+    #[test]
+    fn test_add() {
+        let add = |a: &str, b: &str| (a.parse::<Snafu>().unwrap() + b.parse().unwrap()).to_string();
+        assert_eq!(add("2", "2"), "1-");
+        assert_eq!(add("2222", "1"), "1====");
+        assert_eq!(add("-", "="), "-2");
+        // Negating flips every digit
+        assert_eq!(add("1=-0-2", "-2101="), "0");
+        let numbers = boundary_numbers()
+            .into_iter()
+            .map(|n| n >> 2)
+            .collect::<Vec<i64>>();
+        for &a in &numbers {
+            for &b in &numbers {
+                let sum = Snafu::from(a) + Snafu::from(b);
+                assert_eq!(i64::try_from(&sum), Ok(a + b));
+            }
+        }
+    }
+    #[test]
+    fn test_add_beyond_i64() {
+        let max = Snafu::from(i64::MAX);
+        let sum = &max + &max;
+        assert!(i64::try_from(&sum).is_err());
+        assert_eq!(i128::try_from(&sum), Ok(2 * i64::MAX as i128));
+        let big = (0..1000).map(|_| max.clone()).sum::<Snafu>();
+        assert_eq!(i128::try_from(&big), Ok(1000 * i64::MAX as i128));
+    }
+}
@@ -1,3 +1,7 @@
+pub mod ocr;
+pub mod search;
+pub mod sight;
+
 /// Measures the `Duration` it take to run the given expression.
 /// ```
 /// # #[macro_use] extern crate aoc;
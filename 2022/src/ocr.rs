@@ -0,0 +1,118 @@
+/// Block letters as drawn by the puzzles, one string per row with `#` for a lit pixel.
+pub struct Font {
+    pub width: usize,
+    pub height: usize,
+    pub spacing: usize, // blank columns between letters
+    pub glyphs: &'static [(char, &'static [&'static str])],
+}
+
+/// The 4x6 font, letters 5 columns apart.
+pub const SMALL: Font = Font {
+    width: 4,
+    height: 6,
+    spacing: 1,
+    glyphs: &[
+        ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+        ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+        ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+        ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+        ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+        ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+        ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+        ('I', &[".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+        ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+        ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+        ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+        ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+        ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+        ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+        ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('Y', &["#...", "#...", ".#.#", "..#.", "..#.", "..#."]),
+        ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+    ],
+};
+
+/// The 6x10 font, letters 8 columns apart.
+#[rustfmt::skip]
+pub const LARGE: Font = Font {
+    width: 6,
+    height: 10,
+    spacing: 2,
+    glyphs: &[
+        ('A', &["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+        ('B', &["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+        ('C', &[".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+        ('E', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+        ('F', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+        ('G', &[".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+        ('H', &["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+        ('J', &["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+        ('K', &["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+        ('L', &["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+        ('N', &["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+        ('P', &["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+        ('R', &["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+        ('X', &["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+        ('Z', &["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+    ],
+};
+
+impl Font {
+    // Letter whose top left corner is at column `x` of `rows`, ' ' if the cell is
+    // blank or '?' if it matches no glyph
+    fn letter(&self, rows: &[Vec<bool>], x: usize) -> char {
+        let lit = |r: usize, c: usize| rows.get(r).and_then(|row| row.get(x + c)) == Some(&true);
+        let cell = (0..self.height).flat_map(|r| (0..self.width).map(move |c| (r, c)));
+        if cell.clone().all(|(r, c)| !lit(r, c)) {
+            return ' ';
+        }
+        self.glyphs
+            .iter()
+            .find(|(_, glyph)| {
+                cell.clone()
+                    .all(|(r, c)| lit(r, c) == (glyph[r].as_bytes()[c] == b'#'))
+            })
+            .map_or('?', |&(letter, _)| letter)
+    }
+
+    /// Reads the letters of a drawing, one line of text per `height` rows of pixels.
+    /// Any character other than `.` or a space counts as a lit pixel.
+    /// ```
+    /// use aoc::ocr::SMALL;
+    /// let drawing = [
+    ///     "#..#.####.#....#.....##.",
+    ///     "#..#.#....#....#....#..#",
+    ///     "####.###..#....#....#..#",
+    ///     "#..#.#....#....#....#..#",
+    ///     "#..#.#....#....#....#..#",
+    ///     "#..#.####.####.####..##.",
+    /// ];
+    /// assert_eq!(SMALL.read(&drawing.join("\n")), "HELLO");
+    /// ```
+    pub fn read(&self, drawing: &str) -> String {
+        let rows = drawing
+            .lines()
+            .map(|line| line.chars().map(|c| !matches!(c, '.' | ' ')).collect())
+            .collect::<Vec<Vec<bool>>>();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        rows.chunks(self.height)
+            .map(|band| {
+                let text = (0..columns)
+                    .step_by(self.width + self.spacing)
+                    .map(|x| self.letter(band, x))
+                    .collect::<String>();
+                text.trim_end().to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// Reads a drawing in the large font if it is exactly as tall, otherwise in the small one.
+pub fn read(drawing: &str) -> String {
+    match drawing.lines().count() == LARGE.height {
+        true => LARGE.read(drawing),
+        false => SMALL.read(drawing),
+    }
+}
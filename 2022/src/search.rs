@@ -0,0 +1,35 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    hash::Hash,
+};
+
+/// Breadth-first search from `start` until a state satisfying `is_goal` is found.
+/// Returns the goal state and the number of steps taken to reach it.
+/// ```
+/// use aoc::search::bfs;
+/// let (end, steps) = bfs(1u32, |&n| [n + 1, n * 2], |&n| n == 10).unwrap();
+/// assert_eq!((end, steps), (10, 4));
+/// ```
+pub fn bfs<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<(S, usize)>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut seen = HashSet::from([start.clone()]);
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((state, steps)) = queue.pop_front() {
+        if is_goal(&state) {
+            return Some((state, steps));
+        }
+        for next in successors(&state) {
+            if seen.insert(next.clone()) {
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,112 @@
+/// The four axis directions as (dx, dy), y growing downwards.
+pub const ORTHOGONAL: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// The axis directions followed by the four diagonals.
+pub const ALL_DIRECTIONS: [(isize, isize); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// Rectangular grid of heights stored row by row in a flat vector.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeightMap<T> {
+    heights: Vec<T>,
+    width: usize,
+}
+
+impl<T: Copy + Ord> HeightMap<T> {
+    /// Returns `None` if `heights` does not split into whole rows of `width`.
+    pub fn new(heights: Vec<T>, width: usize) -> Option<Self> {
+        if width == 0 || !heights.len().is_multiple_of(width) {
+            return None;
+        }
+        Some(Self { heights, width })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.heights.len() / self.width
+    }
+
+    pub fn heights(&self) -> &[T] {
+        &self.heights
+    }
+
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    pub fn position(&self, index: usize) -> (usize, usize) {
+        (index % self.width, index / self.width)
+    }
+
+    fn step(&self, index: usize, (dx, dy): (isize, isize)) -> Option<usize> {
+        let (x, y) = self.position(index);
+        let x = x.checked_add_signed(dx).filter(|&x| x < self.width)?;
+        let y = y.checked_add_signed(dy).filter(|&y| y < self.height())?;
+        Some(self.index(x, y))
+    }
+
+    /// Looks from every cell in `direction`, calling `f` with the cell index, the number
+    /// of cells seen before the view is blocked or the edge is reached, and whether the
+    /// view reaches the edge. Each line of sight is swept once from the edge it faces,
+    /// keeping a stack of the cells not yet hidden behind a taller one.
+    pub fn sweep(&self, direction: (isize, isize), mut f: impl FnMut(usize, usize, bool)) {
+        let back = (-direction.0, -direction.1);
+        let mut stack: Vec<(T, usize)> = vec![];
+        for start in 0..self.heights.len() {
+            if self.step(start, direction).is_some() {
+                continue;
+            }
+            stack.clear();
+            let mut cell = Some(start);
+            let mut distance = 0; // from the edge, in steps
+            while let Some(index) = cell {
+                let height = self.heights[index];
+                while stack.last().is_some_and(|&(h, _)| h < height) {
+                    stack.pop();
+                }
+                match stack.last() {
+                    Some(&(_, blocker)) => f(index, distance - blocker, false),
+                    None => f(index, distance, true),
+                }
+                stack.push((height, distance));
+                cell = self.step(index, back);
+                distance += 1;
+            }
+        }
+    }
+
+    /// Whether each cell can be seen from outside the grid along any of `directions`.
+    /// ```
+    /// use aoc::sight::{HeightMap, ORTHOGONAL};
+    /// let map = HeightMap::new(vec![1, 1, 1, 1, 0, 1, 1, 1, 1], 3).unwrap();
+    /// let visible = map.visible(&ORTHOGONAL);
+    /// assert_eq!(visible.iter().filter(|&&v| v).count(), 8);
+    /// ```
+    pub fn visible(&self, directions: &[(isize, isize)]) -> Vec<bool> {
+        let mut visible = vec![false; self.heights.len()];
+        for &direction in directions {
+            self.sweep(direction, |i, _, edge| visible[i] |= edge);
+        }
+        visible
+    }
+
+    /// Product over `directions` of how many cells each cell can see.
+    pub fn scenic_scores(&self, directions: &[(isize, isize)]) -> Vec<u64> {
+        let mut scores = vec![1; self.heights.len()];
+        for &direction in directions {
+            self.sweep(direction, |i, seen, _| scores[i] *= seen as u64);
+        }
+        scores
+    }
+}